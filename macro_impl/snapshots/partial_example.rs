@@ -0,0 +1,171 @@
+///A `Example` where every field but the forced ones is optional.
+#[derive(PartialEq, Clone, Debug)]
+#[allow(clippy::option_option)]
+pub struct PartialExample {
+    ///Value for `Example::id`, always present.
+    pub id: u32,
+    ///Optional value for `Example::food`.
+    pub food: ::std::option::Option<f64>,
+    ///Optional value for `Example::bard`.
+    pub bard: ::std::option::Option<Option<String>>,
+    source: String,
+}
+impl PartialExample {
+    /// Generates an empty `Partial`, only the forced fields are set.
+    #[allow(clippy::new_without_default)]
+    pub fn new(id: u32) -> Self {
+        PartialExample {
+            id: id,
+            food: ::std::option::Option::None,
+            bard: ::std::option::Option::None,
+            source: "template".to_string(),
+        }
+    }
+    /// Generates a `Partial` with the given values.
+    ///
+    /// The forced fields come first. Every other argument accepts either
+    /// the value itself or an `Option`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_arguments<
+        FOOD: ::std::convert::Into<::std::option::Option<f64>>,
+        BARD: ::std::convert::Into<::std::option::Option<Option<String>>>,
+    >(id: u32, food: FOOD, bard: BARD) -> Self {
+        PartialExample {
+            id: id,
+            food: food.into(),
+            bard: bard.into(),
+            source: "template".to_string(),
+        }
+    }
+    ///Returns the extra field `source`.
+    pub fn source(&self) -> &String {
+        &self.source
+    }
+    ///Sets the extra field `source`.
+    pub fn set_source(&mut self, value: String) {
+        self.source = value;
+    }
+    /// add all attributes set here in to the given Partial
+    ///
+    /// If an attribute in here is already set in `obj`, then it gets
+    /// overwritten
+    pub fn merge_into(&self, obj: &mut Self) {
+        obj.id = self.id.clone();
+        if let ::std::option::Option::Some(value) = &self.food {
+            obj.food = ::std::option::Option::Some(value.clone());
+        }
+        if let ::std::option::Option::Some(value) = &self.bard {
+            obj.bard = ::std::option::Option::Some(value.clone());
+        }
+    }
+    /// check all attributes that are present on BOTH objects, if they
+    /// are equal. If there are no attributes present in both, will
+    /// return true.
+    pub fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+        if !::derive_patch::patchable::PatchableField::compare(&self.id, &obj.id) {
+            return false;
+        }
+        if let (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs))
+            = (&self.food, &obj.food) {
+            if !::derive_patch::patchable::PatchableField::compare(lhs, rhs) {
+                return false;
+            }
+        }
+        if let (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs))
+            = (&self.bard, &obj.bard) {
+            if !::derive_patch::patchable::PatchableField::compare(lhs, rhs) {
+                return false;
+            }
+        }
+        true
+    }
+    /**Builds a `Example` out of this partial.
+
+Fails if not all fields are set, see `is_complete`.*/
+    #[allow(clippy::result_unit_err)]
+    pub fn build(&self) -> ::std::result::Result<Example, ()> {
+        ::std::result::Result::Ok(Example {
+            id: self.id.clone(),
+            food: self.food.as_ref().ok_or(())?.clone(),
+            bard: self.bard.as_ref().ok_or(())?.clone(),
+            something_special: 42,
+        })
+    }
+}
+impl ::std::default::Default for PartialExample {
+    fn default() -> Self {
+        PartialExample {
+            id: 5,
+            food: ::std::option::Option::None,
+            bard: ::std::option::Option::None,
+            source: "template".to_string(),
+        }
+    }
+}
+impl ::derive_patch::traits::Base<()> for PartialExample {
+    type Target = Example;
+    const MAX_FIELDS: usize = 1 + 1;
+    fn is_complete(&self) -> bool {
+        self.food.is_some() && self.bard.is_some()
+    }
+    fn is_empty(&self) -> bool {
+        self.food.is_none() && self.bard.is_none()
+    }
+    fn count(&self) -> u32 {
+        self.food.is_some() as u32 + self.bard.is_some() as u32
+    }
+    fn apply(&self, obj: &mut Example) {
+        obj.id = self.id.clone();
+        if let ::std::option::Option::Some(value) = &self.food {
+            obj.food = value.clone();
+        }
+        if let ::std::option::Option::Some(value) = &self.bard {
+            obj.bard = value.clone();
+        }
+    }
+}
+impl ::derive_patch::traits::Partial for PartialExample {
+    fn build(&self) -> ::std::result::Result<Example, ()> {
+        PartialExample::build(self)
+    }
+    fn merge_into(&self, obj: &mut Self) {
+        PartialExample::merge_into(self, obj)
+    }
+    fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+        PartialExample::is_partial_equal_existing(self, obj)
+    }
+}
+impl ::derive_patch::traits::PartialExt for Example {
+    type Partial = PartialExample;
+}
+impl ::std::convert::From<Example> for PartialExample {
+    fn from(value: Example) -> Self {
+        PartialExample {
+            id: value.id,
+            food: ::std::option::Option::Some(value.food),
+            bard: ::std::option::Option::Some(value.bard),
+            source: "template".to_string(),
+        }
+    }
+}
+impl ::std::convert::TryFrom<PartialExample> for Example {
+    type Error = ::derive_patch::mismatch::IncompleteError<PartialExample>;
+    fn try_from(value: PartialExample) -> ::std::result::Result<Self, Self::Error> {
+        if <PartialExample as ::derive_patch::traits::Base<()>>::is_complete(&value) {
+            ::std::result::Result::Ok(Example {
+                id: value.id,
+                food: value.food.unwrap(),
+                bard: value.bard.unwrap(),
+                something_special: 42,
+            })
+        } else {
+            ::std::result::Result::Err(
+                ::derive_patch::mismatch::IncompleteError::new(
+                    "try_from",
+                    "Example",
+                    value,
+                ),
+            )
+        }
+    }
+}
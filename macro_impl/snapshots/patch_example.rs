@@ -0,0 +1,516 @@
+impl ::derive_patch::patchable::PatchableField for Example
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{}
+impl ::derive_patch::traits::PatchableExt for Example
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{
+    type Patch = ExamplePatch;
+    fn get_patch(&self, rhs: &Self) -> ExamplePatch {
+        ExamplePatch {
+            food: {
+                let diff = <NumericDistanceDiff<
+                    f64,
+                > as ::derive_patch::diff::Diff>::new(&self.food, &rhs.food);
+                if ::derive_patch::diff::Diff::contains_change(&diff) {
+                    ::std::option::Option::Some(diff)
+                } else {
+                    ::std::option::Option::None
+                }
+            },
+            bard: {
+                let diff = <::derive_patch::diff::CopyDiff<
+                    Option<String>,
+                > as ::derive_patch::diff::Diff>::new(&self.bard, &rhs.bard);
+                if ::derive_patch::diff::Diff::contains_change(&diff) {
+                    ::std::option::Option::Some(diff)
+                } else {
+                    ::std::option::Option::None
+                }
+            },
+            id: ::std::clone::Clone::clone(&self.id),
+            timestamp: <u64 as ::std::default::Default>::default(),
+            patch_id: 1,
+        }
+    }
+}
+///A patch for `Example`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExamplePatch
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{
+    food: ::std::option::Option<NumericDistanceDiff<f64>>,
+    bard: ::std::option::Option<::derive_patch::diff::CopyDiff<Option<String>>>,
+    ///Has to match `id` of the patched object.
+    id: String,
+    timestamp: u64,
+    patch_id: u32,
+}
+impl ExamplePatch
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{
+    /// Generates an empty patch for the object with the given ids.
+    #[allow(clippy::new_without_default)]
+    pub fn new(id: String) -> Self {
+        ExamplePatch {
+            food: ::std::option::Option::None,
+            bard: ::std::option::Option::None,
+            id: id,
+            timestamp: <u64 as ::std::default::Default>::default(),
+            patch_id: 1,
+        }
+    }
+    /// Generates a patch that changes `obj` to the values set in
+    /// `partial`.
+    ///
+    /// Fields of `partial` that equal the current value of `obj` are
+    /// left out.
+    pub fn new_with_partial(
+        obj: &Example,
+        partial: &PartialExample,
+        id: String,
+    ) -> Self {
+        ExamplePatch {
+            food: match &partial.food {
+                ::std::option::Option::Some(value) => {
+                    let diff = <NumericDistanceDiff<
+                        f64,
+                    > as ::derive_patch::diff::Diff>::new(&obj.food, value);
+                    if ::derive_patch::diff::Diff::contains_change(&diff) {
+                        ::std::option::Option::Some(diff)
+                    } else {
+                        ::std::option::Option::None
+                    }
+                }
+                ::std::option::Option::None => ::std::option::Option::None,
+            },
+            bard: match &partial.bard {
+                ::std::option::Option::Some(value) => {
+                    let diff = <::derive_patch::diff::CopyDiff<
+                        Option<String>,
+                    > as ::derive_patch::diff::Diff>::new(&obj.bard, value);
+                    if ::derive_patch::diff::Diff::contains_change(&diff) {
+                        ::std::option::Option::Some(diff)
+                    } else {
+                        ::std::option::Option::None
+                    }
+                }
+                ::std::option::Option::None => ::std::option::Option::None,
+            },
+            id: id,
+            timestamp: <u64 as ::std::default::Default>::default(),
+            patch_id: 1,
+        }
+    }
+    ///Returns the extra field `timestamp`.
+    pub fn timestamp(&self) -> &u64 {
+        &self.timestamp
+    }
+    ///Sets the extra field `timestamp`.
+    pub fn set_timestamp(&mut self, value: u64) {
+        self.timestamp = value;
+    }
+    ///Returns the extra field `patch_id`.
+    pub fn patch_id(&self) -> &u32 {
+        &self.patch_id
+    }
+    ///Sets the extra field `patch_id`.
+    pub fn set_patch_id(&mut self, value: u32) {
+        self.patch_id = value;
+    }
+    /// internal apply function. Responsible for actually assigning the
+    /// values.
+    ///
+    /// This function performs no checks, it's the responsibility of the
+    /// caller to perform checks.
+    ///
+    /// Used by `apply`, after `can_apply_cleanly` succeeded.
+    fn _apply(&self, obj: &mut Example) {
+        if let ::std::option::Option::Some(diff) = &self.food {
+            let applied = ::derive_patch::diff::Diff::apply_into(diff, &mut obj.food);
+            ::std::debug_assert!(applied.is_ok(), "the diff was checked before");
+        }
+        if let ::std::option::Option::Some(diff) = &self.bard {
+            let applied = ::derive_patch::diff::Diff::apply_into(diff, &mut obj.bard);
+            ::std::debug_assert!(applied.is_ok(), "the diff was checked before");
+        }
+    }
+}
+impl ::derive_patch::traits::Base<
+    ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError>,
+> for ExamplePatch
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{
+    type Target = Example;
+    /**Available fields
+- `food`
+- `bard`*/
+    const MAX_FIELDS: usize = 2usize;
+    /// returns true if ALL fields have an associated diff.
+    fn is_complete(&self) -> bool {
+        self.food.is_some() && self.bard.is_some()
+    }
+    fn is_empty(&self) -> bool {
+        self.food.is_none() && self.bard.is_none()
+    }
+    fn count(&self) -> u32 {
+        let mut count = 0;
+        if self.food.is_some() {
+            count += 1;
+        }
+        if self.bard.is_some() {
+            count += 1;
+        }
+        count
+    }
+    /// applies the patch to the given object
+    ///
+    /// makes sure the id fields of the object match the ones of this
+    /// patch. Returns an Error if that is not the case.
+    ///
+    /// makes sure that all old_values of the patches field are the
+    /// current value of the given object. Returns an Error otherwise.
+    ///
+    /// This function does not change `obj` if an Error occurs.
+    fn apply(
+        &self,
+        obj: &mut Example,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        ::derive_patch::traits::Patch::is_correct_target(self, obj)?;
+        ::derive_patch::traits::Patch::can_apply_cleanly(self, obj)?;
+        self._apply(obj);
+        ::std::result::Result::Ok(())
+    }
+}
+impl ::derive_patch::traits::Patch for ExamplePatch
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+{
+    fn cleanup(&mut self) -> bool {
+        let mut changed = false;
+        if let ::std::option::Option::Some(diff) = &mut self.food {
+            if ::derive_patch::diff::Diff::cleanup(diff) {
+                changed = true;
+            }
+            if !::derive_patch::diff::Diff::contains_change(diff) {
+                self.food = ::std::option::Option::None;
+                changed = true;
+            }
+        }
+        if let ::std::option::Option::Some(diff) = &mut self.bard {
+            if ::derive_patch::diff::Diff::cleanup(diff) {
+                changed = true;
+            }
+            if !::derive_patch::diff::Diff::contains_change(diff) {
+                self.bard = ::std::option::Option::None;
+                changed = true;
+            }
+        }
+        changed
+    }
+    fn is_correct_target(
+        &self,
+        obj: &Example,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+        if !::derive_patch::patchable::PatchableField::compare(&self.id, &obj.id) {
+            error
+                .add_error(
+                    ::derive_patch::mismatch::MismatchError::new(
+                        "id",
+                        ::std::format!("{:?}", obj.id),
+                        ::std::format!("{:?}", self.id),
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                    ),
+                );
+        }
+        if error.is_error_free() {
+            ::std::result::Result::Ok(())
+        } else {
+            ::std::result::Result::Err(error)
+        }
+    }
+    fn can_apply_cleanly(
+        &self,
+        obj: &Example,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+        if let ::std::option::Option::Some(diff) = &self.food {
+            if let ::std::result::Result::Err(mut e)
+                = <NumericDistanceDiff<
+                    f64,
+                > as ::derive_patch::diff::Diff>::check(diff, &obj.food) {
+                e.prefix_fields("food");
+                error.merge(&e);
+            }
+        }
+        if let ::std::option::Option::Some(diff) = &self.bard {
+            if let ::std::result::Result::Err(mut e)
+                = <::derive_patch::diff::CopyDiff<
+                    Option<String>,
+                > as ::derive_patch::diff::Diff>::check(diff, &obj.bard) {
+                e.prefix_fields("bard");
+                error.merge(&e);
+            }
+        }
+        if error.is_error_free() {
+            ::std::result::Result::Ok(())
+        } else {
+            ::std::result::Result::Err(error)
+        }
+    }
+    fn is_same_target(&self, other: &Self) -> bool {
+        ::derive_patch::patchable::PatchableField::compare(&self.id, &other.id)
+    }
+    fn coalesce(&mut self, next: &Self) -> bool {
+        if !(self.food.is_some() == next.food.is_some()
+            && self.bard.is_some() == next.bard.is_some())
+            || !::derive_patch::traits::Patch::is_same_target(self, next)
+        {
+            return false;
+        }
+        let merged_0 = match (&self.food, &next.food) {
+            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                let mut lhs = ::std::clone::Clone::clone(lhs);
+                if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                    return false;
+                }
+                ::std::option::Option::Some(lhs)
+            }
+            _ => ::std::option::Option::None,
+        };
+        let merged_1 = match (&self.bard, &next.bard) {
+            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                let mut lhs = ::std::clone::Clone::clone(lhs);
+                if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                    return false;
+                }
+                ::std::option::Option::Some(lhs)
+            }
+            _ => ::std::option::Option::None,
+        };
+        self.food = merged_0;
+        self.bard = merged_1;
+        true
+    }
+    fn merge_into(
+        &self,
+        target: &mut Self,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+        if !::derive_patch::patchable::PatchableField::compare(&self.id, &target.id) {
+            error
+                .add_error(
+                    ::derive_patch::mismatch::MismatchError::new(
+                        "id",
+                        ::std::format!("{:?}", target.id),
+                        ::std::format!("{:?}", self.id),
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                    ),
+                );
+        }
+        if error.has_errors() {
+            return ::std::result::Result::Err(error);
+        }
+        let merged_0 = match (&target.food, &self.food) {
+            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                let mut lhs = ::std::clone::Clone::clone(lhs);
+                if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                    error
+                        .add_error(
+                            ::derive_patch::mismatch::MismatchError::new(
+                                "food",
+                                "a change of the merged value",
+                                "a conflicting change",
+                                ::derive_patch::mismatch::MismatchType::MergeConflict,
+                            ),
+                        );
+                }
+                ::std::option::Option::Some(lhs)
+            }
+            (lhs, rhs) => ::std::option::Option::or(lhs.as_ref(), rhs.as_ref()).cloned(),
+        };
+        let merged_1 = match (&target.bard, &self.bard) {
+            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                let mut lhs = ::std::clone::Clone::clone(lhs);
+                if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                    error
+                        .add_error(
+                            ::derive_patch::mismatch::MismatchError::new(
+                                "bard",
+                                "a change of the merged value",
+                                "a conflicting change",
+                                ::derive_patch::mismatch::MismatchType::MergeConflict,
+                            ),
+                        );
+                }
+                ::std::option::Option::Some(lhs)
+            }
+            (lhs, rhs) => ::std::option::Option::or(lhs.as_ref(), rhs.as_ref()).cloned(),
+        };
+        if error.has_errors() {
+            return ::std::result::Result::Err(error);
+        }
+        target.food = merged_0;
+        target.bard = merged_1;
+        ::std::result::Result::Ok(())
+    }
+    fn overwrite_into(
+        &self,
+        target: &mut Self,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+        if !::derive_patch::patchable::PatchableField::compare(&self.id, &target.id) {
+            error
+                .add_error(
+                    ::derive_patch::mismatch::MismatchError::new(
+                        "id",
+                        ::std::format!("{:?}", target.id),
+                        ::std::format!("{:?}", self.id),
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                    ),
+                );
+        }
+        if error.has_errors() {
+            return ::std::result::Result::Err(error);
+        }
+        if let ::std::option::Option::Some(diff) = &self.food {
+            target.food = ::std::option::Option::Some(::std::clone::Clone::clone(diff));
+        }
+        if let ::std::option::Option::Some(diff) = &self.bard {
+            target.bard = ::std::option::Option::Some(::std::clone::Clone::clone(diff));
+        }
+        ::std::result::Result::Ok(())
+    }
+    fn rebase(
+        &mut self,
+        obj: &Example,
+    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+        ::derive_patch::traits::Patch::is_correct_target(self, obj)?;
+        let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+        let merged_0 = match &self.food {
+            ::std::option::Option::Some(diff) => {
+                let mut diff = ::std::clone::Clone::clone(diff);
+                if let ::std::result::Result::Err(mut e)
+                    = <NumericDistanceDiff<
+                        f64,
+                    > as ::derive_patch::diff::Diff>::rebase(&mut diff, &obj.food) {
+                    e.prefix_fields("food");
+                    error.merge(&e);
+                }
+                ::std::option::Option::Some(diff)
+            }
+            ::std::option::Option::None => ::std::option::Option::None,
+        };
+        let merged_1 = match &self.bard {
+            ::std::option::Option::Some(diff) => {
+                let mut diff = ::std::clone::Clone::clone(diff);
+                if let ::std::result::Result::Err(mut e)
+                    = <::derive_patch::diff::CopyDiff<
+                        Option<String>,
+                    > as ::derive_patch::diff::Diff>::rebase(&mut diff, &obj.bard) {
+                    e.prefix_fields("bard");
+                    error.merge(&e);
+                }
+                ::std::option::Option::Some(diff)
+            }
+            ::std::option::Option::None => ::std::option::Option::None,
+        };
+        if error.has_errors() {
+            return ::std::result::Result::Err(error);
+        }
+        self.food = merged_0;
+        self.bard = merged_1;
+        ::std::result::Result::Ok(())
+    }
+    fn merge_concurrent<R>(
+        &self,
+        other: &Self,
+        resolver: &mut R,
+    ) -> ::std::result::Result<Self, ::std::vec::Vec<::derive_patch::conflict::Conflict>>
+    where
+        R: ::derive_patch::conflict::ConflictResolver + ?::std::marker::Sized,
+    {
+        let mut conflicts = ::std::vec::Vec::new();
+        if !::derive_patch::patchable::PatchableField::compare(&self.id, &other.id) {
+            conflicts
+                .push(
+                    ::derive_patch::conflict::Conflict::new(
+                        "id",
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                        false,
+                    ),
+                );
+        }
+        if !conflicts.is_empty() {
+            return ::std::result::Result::Err(conflicts);
+        }
+        let merged_0 = match (&self.food, &other.food) {
+            (::std::option::Option::Some(ours), ::std::option::Option::Some(theirs)) => {
+                ::derive_patch::conflict::resolve_field(
+                    "food",
+                    ours,
+                    theirs,
+                    resolver,
+                    &mut conflicts,
+                )
+            }
+            (ours, theirs) => {
+                ::std::option::Option::or(ours.as_ref(), theirs.as_ref()).cloned()
+            }
+        };
+        let merged_1 = match (&self.bard, &other.bard) {
+            (::std::option::Option::Some(ours), ::std::option::Option::Some(theirs)) => {
+                ::derive_patch::conflict::resolve_field(
+                    "bard",
+                    ours,
+                    theirs,
+                    resolver,
+                    &mut conflicts,
+                )
+            }
+            (ours, theirs) => {
+                ::std::option::Option::or(ours.as_ref(), theirs.as_ref()).cloned()
+            }
+        };
+        if !conflicts.is_empty() {
+            return ::std::result::Result::Err(conflicts);
+        }
+        ::std::result::Result::Ok(ExamplePatch {
+            food: merged_0,
+            bard: merged_1,
+            id: ::std::clone::Clone::clone(&self.id),
+            timestamp: ::std::clone::Clone::clone(&self.timestamp),
+            patch_id: ::std::clone::Clone::clone(&self.patch_id),
+        })
+    }
+}
+impl ::derive_patch::traits::InvertPatch for ExamplePatch
+where
+    for<'__deferred> NumericDistanceDiff<f64>: ::derive_patch::diff::Diff<Object = f64>,
+    for<'__invert> NumericDistanceDiff<f64>: ::derive_patch::diff::Invert,
+    for<'__invert> ::derive_patch::diff::CopyDiff<
+        Option<String>,
+    >: ::derive_patch::diff::Invert,
+{
+    fn invert(&self) -> Self {
+        ExamplePatch {
+            food: self.food.as_ref().map(::derive_patch::diff::Invert::invert),
+            bard: self.bard.as_ref().map(::derive_patch::diff::Invert::invert),
+            id: ::std::clone::Clone::clone(&self.id),
+            timestamp: ::std::clone::Clone::clone(&self.timestamp),
+            patch_id: ::std::clone::Clone::clone(&self.patch_id),
+        }
+    }
+}
+const _: fn() = || {
+    fn diff_object_must_match_field_type<D, T>()
+    where
+        D: ::derive_patch::diff::Diff<Object = T>,
+    {}
+    diff_object_must_match_field_type::<NumericDistanceDiff<f64>, f64>();
+};
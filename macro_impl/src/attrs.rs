@@ -0,0 +1,591 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Parsing of the `#[patch(...)]` and `#[partial(...)]` helper attributes.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token::Comma,
+    Attribute, Error, Expr, Ident, LitStr, Meta, NestedMeta, Path, PathArguments, Result, Token,
+    Type, Visibility, WherePredicate,
+};
+
+/// Options of a single field, set via `#[patch(...)]`.
+#[derive(Default)]
+pub(crate) struct PatchFieldAttrs {
+    /// The `Diff` to use for this field, set via `diff = Type`.
+    ///
+    /// `None` falls back to `CopyDiff`.
+    pub(crate) diff: Option<Type>,
+    /// Set via `id`, the field identifies the patched object.
+    ///
+    /// Id fields are copied into the patch instead of being diffed.
+    pub(crate) id: Option<Ident>,
+    /// Set via `skip`, the field is not part of the patch.
+    pub(crate) skip: Option<Ident>,
+    /// Set via `nested`, the field type derives `Patch` itself and is diffed
+    /// with a `NestedPatchDiff`.
+    pub(crate) nested: Option<Ident>,
+}
+
+impl PatchFieldAttrs {
+    /// Collects the options of all `#[patch(...)]` attributes in `attrs`.
+    pub(crate) fn parse(attrs: &[Attribute]) -> Result<PatchFieldAttrs> {
+        let mut result = PatchFieldAttrs::default();
+
+        for option in parse_options::<PatchFieldOption>(attrs, "patch")? {
+            match option {
+                PatchFieldOption::Diff(key, diff) => set_once(&mut result.diff, &key, *diff)?,
+                PatchFieldOption::Id(key) => set_once(&mut result.id, &key, key.clone())?,
+                PatchFieldOption::Skip(key) => set_once(&mut result.skip, &key, key.clone())?,
+                PatchFieldOption::Nested(key) => set_once(&mut result.nested, &key, key.clone())?,
+            }
+        }
+
+        if let Some(skip) = &result.skip {
+            if let Some(id) = &result.id {
+                let mut error = Error::new_spanned(id, "`id` fields can not be skipped");
+                error.combine(Error::new_spanned(skip, "field is skipped here"));
+                return Err(error);
+            }
+            if let Some(diff) = &result.diff {
+                return Err(conflict(skip, diff));
+            }
+        }
+        if let Some(nested) = &result.nested {
+            if let Some(skip) = &result.skip {
+                return Err(conflict(nested, skip));
+            }
+            if let Some(id) = &result.id {
+                return Err(conflict(nested, id));
+            }
+            if let Some(diff) = &result.diff {
+                return Err(conflict(nested, diff));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// True if the field is stored as a `CopyDiff`.
+    pub(crate) fn is_copy_diff(&self) -> bool {
+        match (&self.nested, &self.diff) {
+            (Some(_), _) => false,
+            (None, None) => true,
+            (None, Some(Type::Path(diff))) => diff
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "CopyDiff"),
+            (None, Some(_)) => false,
+        }
+    }
+
+    /// The type of the `Diff` that stores changes of a field of type `ty`.
+    ///
+    /// The diffs of this crate that are generic over the field type (see
+    /// `GENERIC_DIFFS`) get the field type as their argument if written
+    /// without one, e.g. `NumericDistanceDiff`. Everything else is used as
+    /// written. `nested` fields use a `NestedPatchDiff`.
+    pub(crate) fn diff_type(&self, ty: &Type) -> TokenStream {
+        if self.nested.is_some() {
+            return quote!(::derive_patch::diff::NestedPatchDiff<#ty>);
+        }
+        match &self.diff {
+            None => quote!(::derive_patch::diff::CopyDiff<#ty>),
+            Some(Type::Path(diff)) if is_generic_diff(diff) => quote!(#diff<#ty>),
+            Some(diff) => diff.to_token_stream(),
+        }
+    }
+
+    /// The function of `derive_patch::conflict` that resolves a field both
+    /// patches of `merge_concurrent` change. `nested` fields merge their
+    /// patches concurrently as well.
+    pub(crate) fn resolve_fn(&self) -> TokenStream {
+        if self.nested.is_some() {
+            quote!(::derive_patch::conflict::resolve_nested)
+        } else {
+            quote!(::derive_patch::conflict::resolve_field)
+        }
+    }
+}
+
+/// A single `key = value` entry of a `#[patch(...)]` field attribute.
+enum PatchFieldOption {
+    /// `diff = Type`
+    Diff(Ident, Box<Type>),
+    /// `id`
+    Id(Ident),
+    /// `skip`
+    Skip(Ident),
+    /// `nested`
+    Nested(Ident),
+}
+
+impl Parse for PatchFieldOption {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key: Ident = input.parse()?;
+        match key.to_string().as_str() {
+            "diff" => {
+                input.parse::<Token![=]>()?;
+                Ok(PatchFieldOption::Diff(key, input.parse()?))
+            }
+            "id" => Ok(PatchFieldOption::Id(key)),
+            "skip" => Ok(PatchFieldOption::Skip(key)),
+            "nested" => Ok(PatchFieldOption::Nested(key)),
+            _ => Err(unknown(&key, "patch", &["diff", "id", "skip", "nested"])),
+        }
+    }
+}
+
+/// Options of a single field, set via `#[partial(...)]`.
+#[derive(Default)]
+pub(crate) struct PartialFieldAttrs {
+    /// Set via `skip`, the field is not part of the partial.
+    pub(crate) skip: Option<Ident>,
+    /// Set via `force`, the field is always present in the partial.
+    ///
+    /// Forced fields are not wrapped in an `Option` and have to be passed to
+    /// the constructors of the partial.
+    pub(crate) force: Option<Ident>,
+    /// Set via `nested`, the field type derives `Partial` itself and the
+    /// partial stores the partial of that type.
+    pub(crate) nested: Option<Ident>,
+    /// Set via `default` or `default = expr`.
+    ///
+    /// The value of a skipped field, or of a forced field in the `Default`
+    /// implementation of the partial.
+    pub(crate) default: Option<(Ident, Option<Expr>)>,
+}
+
+impl PartialFieldAttrs {
+    /// Collects the options of all `#[partial(...)]` attributes in `attrs`.
+    pub(crate) fn parse(attrs: &[Attribute]) -> Result<PartialFieldAttrs> {
+        let mut result = PartialFieldAttrs::default();
+
+        for option in parse_options::<PartialFieldOption>(attrs, "partial")? {
+            match option {
+                PartialFieldOption::Skip(key) => set_once(&mut result.skip, &key, key.clone())?,
+                PartialFieldOption::Force(key) => set_once(&mut result.force, &key, key.clone())?,
+                PartialFieldOption::Nested(key) => set_once(&mut result.nested, &key, key.clone())?,
+                PartialFieldOption::Default(key, expr) => set_once(
+                    &mut result.default,
+                    &key,
+                    (key.clone(), expr.map(|expr| *expr)),
+                )?,
+            }
+        }
+
+        if let (Some(skip), Some(force)) = (&result.skip, &result.force) {
+            return Err(conflict(skip, force));
+        }
+        if let Some(nested) = &result.nested {
+            if let Some(skip) = &result.skip {
+                return Err(conflict(nested, skip));
+            }
+            if let Some(force) = &result.force {
+                return Err(conflict(nested, force));
+            }
+        }
+        if let (None, None, Some((default, _))) = (&result.skip, &result.force, &result.default) {
+            return Err(Error::new_spanned(
+                default,
+                "`default` is only allowed on `skip` or `force` fields",
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// The value of a skipped field when building the original struct.
+    ///
+    /// Either the expression given via `default = expr` or
+    /// `Default::default()`.
+    pub(crate) fn default_value(&self, ty: &Type) -> TokenStream {
+        match &self.default {
+            Some((_, Some(expr))) => expr.to_token_stream(),
+            Some((key, None)) => default_trait(key, ty),
+            None => default_trait(self.skip.as_ref().expect("skipped field"), ty),
+        }
+    }
+
+    /// The value of a forced field in `Default::default()` of the partial.
+    ///
+    /// `None` if the field has no `default`, the partial cannot implement
+    /// `Default` then.
+    pub(crate) fn forced_default(&self, ty: &Type) -> Option<TokenStream> {
+        self.default.as_ref().map(|_| self.default_value(ty))
+    }
+}
+
+/// A single entry of a `#[partial(...)]` field attribute.
+enum PartialFieldOption {
+    /// `skip`
+    Skip(Ident),
+    /// `force`
+    Force(Ident),
+    /// `nested`
+    Nested(Ident),
+    /// `default` or `default = expr`
+    Default(Ident, Option<Box<Expr>>),
+}
+
+impl Parse for PartialFieldOption {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key: Ident = input.parse()?;
+        match key.to_string().as_str() {
+            "skip" => Ok(PartialFieldOption::Skip(key)),
+            "force" => Ok(PartialFieldOption::Force(key)),
+            "nested" => Ok(PartialFieldOption::Nested(key)),
+            "default" => {
+                if input.peek(Token![=]) {
+                    input.parse::<Token![=]>()?;
+                    Ok(PartialFieldOption::Default(key, Some(input.parse()?)))
+                } else {
+                    Ok(PartialFieldOption::Default(key, None))
+                }
+            }
+            _ => Err(unknown(
+                &key,
+                "partial",
+                &["skip", "force", "nested", "default"],
+            )),
+        }
+    }
+}
+
+/// Options of the generated type, set via `#[patch(...)]` or `#[partial(...)]`
+/// on the struct itself.
+#[derive(Default)]
+pub(crate) struct StructAttrs {
+    /// Fields that only exist on the generated type, set via
+    /// `extra(name: Type, other: Type = expr)`.
+    pub(crate) extra: Vec<ExtraField>,
+    /// The where-clause of the generated type, set via `bound = "T: Trait"`.
+    ///
+    /// Replaces the bounds the derive adds for the generic parameters.
+    pub(crate) bound: Option<Punctuated<WherePredicate, Comma>>,
+    /// The name of the generated type, set via `name = "Name"`.
+    pub(crate) name: Option<Ident>,
+    /// The visibility of the generated type, set via `vis = "pub(crate)"`.
+    pub(crate) vis: Option<Visibility>,
+    /// Additional derives of the generated type, set via `derive(Hash, Eq)`.
+    pub(crate) derive: Vec<Path>,
+    /// Additional attributes of the generated type, set via `attr(...)`.
+    pub(crate) attr: Vec<TokenStream>,
+    /// The attributes of the fields copied to the generated type, set via
+    /// `forward_attrs(serde, doc)`.
+    ///
+    /// `None` forwards `FORWARDED_ATTRS`.
+    pub(crate) forward_attrs: Option<Vec<Path>>,
+}
+
+impl StructAttrs {
+    /// Collects the options of all attributes named `name` in `attrs`.
+    pub(crate) fn parse(attrs: &[Attribute], name: &str) -> Result<StructAttrs> {
+        let mut result = StructAttrs::default();
+        let mut extra_key = None;
+
+        for option in parse_options::<StructOption>(attrs, name)? {
+            match option {
+                StructOption::Extra(key, fields) => {
+                    set_once(&mut extra_key, &key, key.clone())?;
+                    result.extra.extend(fields);
+                }
+                StructOption::Bound(key, bound) => set_once(&mut result.bound, &key, bound)?,
+                StructOption::Name(key, name) => set_once(&mut result.name, &key, name)?,
+                StructOption::Vis(key, vis) => set_once(&mut result.vis, &key, vis)?,
+                StructOption::Derive(derives) => result.derive.extend(derives),
+                StructOption::Attr(attr) => result.attr.push(attr),
+                StructOption::ForwardAttrs(key, paths) => {
+                    set_once(&mut result.forward_attrs, &key, paths.into_iter().collect())?
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The name of the generated type, `default` if not set.
+    pub(crate) fn name(&self, default: Ident) -> Ident {
+        self.name.clone().unwrap_or(default)
+    }
+
+    /// The visibility of the generated type, the one of the original type if
+    /// not set.
+    pub(crate) fn vis<'a>(&'a self, original: &'a Visibility) -> &'a Visibility {
+        self.vis.as_ref().unwrap_or(original)
+    }
+
+    /// The additional derives and attributes of the generated type.
+    ///
+    /// With the `serde` feature of `derive_patch`, which enables the one of
+    /// this crate, the type derives `Serialize` and `Deserialize` through the
+    /// serde that `derive_patch` re-exports.
+    pub(crate) fn type_attrs(&self) -> TokenStream {
+        let serde = if cfg!(feature = "serde") {
+            quote! {
+                #[derive(::derive_patch::serde::Serialize, ::derive_patch::serde::Deserialize)]
+                #[serde(crate = "::derive_patch::serde")]
+            }
+        } else {
+            quote!()
+        };
+        let derive = if self.derive.is_empty() {
+            quote!()
+        } else {
+            let derives = &self.derive;
+            quote!(#[derive(#(#derives),*)])
+        };
+        let attrs = &self.attr;
+        quote!(#serde #derive #(#[#attrs])*)
+    }
+
+    /// The attributes in `attrs` of a field that are copied to the field of
+    /// the generated type.
+    ///
+    /// `#[cfg_attr(...)]` is copied if all the attributes it adds are.
+    /// `#[cfg(...)]` is not part of this, see `cfg_attrs`.
+    pub(crate) fn forwarded(&self, attrs: &[Attribute]) -> TokenStream {
+        let is_forwarded = |path: &Path| match &self.forward_attrs {
+            Some(paths) => paths.contains(path),
+            None => FORWARDED_ATTRS.iter().any(|name| path.is_ident(name)),
+        };
+        let forwarded = attrs.iter().filter(|attr| {
+            if attr.path.is_ident("cfg_attr") {
+                cfg_attr_paths(attr).is_some_and(|paths| paths.iter().all(is_forwarded))
+            } else {
+                !attr.path.is_ident("cfg") && is_forwarded(&attr.path)
+            }
+        });
+        quote!(#(#forwarded)*)
+    }
+}
+
+/// The field attributes forwarded to the generated types if there is no
+/// `forward_attrs(...)`.
+const FORWARDED_ATTRS: &[&str] = &["doc", "serde"];
+
+/// The paths of the attributes a `#[cfg_attr(predicate, attrs...)]` adds.
+///
+/// `None` if the attribute can not be parsed.
+fn cfg_attr_paths(attr: &Attribute) -> Option<Vec<Path>> {
+    match attr.parse_meta().ok()? {
+        Meta::List(list) => list
+            .nested
+            .iter()
+            .skip(1)
+            .map(|nested| match nested {
+                NestedMeta::Meta(meta) => Some(meta.path().clone()),
+                NestedMeta::Lit(_) => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// The `#[cfg(...)]` attributes in `attrs` of a field.
+///
+/// The field only exists if they are enabled, so they are put on all code
+/// generated for the field.
+pub(crate) fn cfg_attrs(attrs: &[Attribute]) -> TokenStream {
+    let cfgs = attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+    quote!(#(#cfgs)*)
+}
+
+/// Fails if `attrs` contain `#[cfg(...)]`, for fields whose code can not be
+/// made conditional, e.g. the fields of tuple structs.
+pub(crate) fn reject_cfg(attrs: &[Attribute], kind: &str) -> Result<()> {
+    match attrs.iter().find(|attr| attr.path.is_ident("cfg")) {
+        Some(cfg) => Err(Error::new_spanned(
+            cfg,
+            format!("`cfg` is not supported on {}", kind),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// A single entry of a `#[patch(...)]` or `#[partial(...)]` struct attribute.
+enum StructOption {
+    /// `extra(name: Type, ...)`
+    Extra(Ident, Punctuated<ExtraField, Comma>),
+    /// `bound = "T: Trait, ..."`
+    Bound(Ident, Punctuated<WherePredicate, Comma>),
+    /// `name = "Name"`
+    Name(Ident, Ident),
+    /// `vis = "pub(crate)"`
+    Vis(Ident, Visibility),
+    /// `derive(Trait, ...)`
+    Derive(Punctuated<Path, Comma>),
+    /// `attr(...)`, the content is the attribute
+    Attr(TokenStream),
+    /// `forward_attrs(path, ...)`
+    ForwardAttrs(Ident, Punctuated<Path, Comma>),
+}
+
+impl Parse for StructOption {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key: Ident = input.parse()?;
+        match key.to_string().as_str() {
+            "extra" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let fields = Punctuated::parse_terminated(&content)?;
+                Ok(StructOption::Extra(key, fields))
+            }
+            "bound" => {
+                input.parse::<Token![=]>()?;
+                let bound: LitStr = input.parse()?;
+                let predicates = bound.parse_with(Punctuated::parse_terminated)?;
+                Ok(StructOption::Bound(key, predicates))
+            }
+            "name" => {
+                input.parse::<Token![=]>()?;
+                let name: LitStr = input.parse()?;
+                Ok(StructOption::Name(key, name.parse()?))
+            }
+            "vis" => {
+                input.parse::<Token![=]>()?;
+                let vis: LitStr = input.parse()?;
+                Ok(StructOption::Vis(key, vis.parse()?))
+            }
+            "derive" => {
+                let content;
+                syn::parenthesized!(content in input);
+                Ok(StructOption::Derive(Punctuated::parse_terminated(
+                    &content,
+                )?))
+            }
+            "attr" => {
+                let content;
+                syn::parenthesized!(content in input);
+                Ok(StructOption::Attr(content.parse()?))
+            }
+            "forward_attrs" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let paths = Punctuated::parse_terminated(&content)?;
+                Ok(StructOption::ForwardAttrs(key, paths))
+            }
+            _ => Err(unknown(
+                &key,
+                "struct",
+                &[
+                    "extra",
+                    "bound",
+                    "name",
+                    "vis",
+                    "derive",
+                    "attr",
+                    "forward_attrs",
+                ],
+            )),
+        }
+    }
+}
+
+/// A field that only exists on the generated type, e.g. a timestamp.
+///
+/// Written as `name: Type` or `name: Type = expr`.
+pub(crate) struct ExtraField {
+    pub(crate) ident: Ident,
+    pub(crate) ty: Type,
+    /// The initial value, `Default::default()` if not given.
+    pub(crate) init: Option<Expr>,
+}
+
+impl ExtraField {
+    /// The value the field is initialised with by the constructors.
+    pub(crate) fn init_value(&self) -> TokenStream {
+        match &self.init {
+            Some(expr) => expr.to_token_stream(),
+            None => default_trait(&self.ident, &self.ty),
+        }
+    }
+}
+
+impl Parse for ExtraField {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        let init = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(ExtraField { ident, ty, init })
+    }
+}
+
+/// `Default::default()` for `ty`, fails to compile at `key` if `ty` does not
+/// implement `Default`.
+fn default_trait(key: &Ident, ty: &Type) -> TokenStream {
+    quote_spanned! {key.span()=>
+        <#ty as ::std::default::Default>::default()
+    }
+}
+
+/// Error for two options that cannot be used together.
+fn conflict<T: ToTokens>(first: &Ident, second: &T) -> Error {
+    Error::new_spanned(second, format!("cannot be combined with `{}`", first))
+}
+
+/// Error for an unknown option `key` of a `kind` attribute, listing the
+/// `expected` options.
+fn unknown(key: &Ident, kind: &str, expected: &[&str]) -> Error {
+    let expected: Vec<String> = expected
+        .iter()
+        .map(|option| format!("`{}`", option))
+        .collect();
+    Error::new_spanned(
+        key,
+        format!(
+            "unknown {} attribute `{}`, expected one of {}",
+            kind,
+            key,
+            expected.join(", ")
+        ),
+    )
+}
+
+/// Parses all attributes named `name` as a comma separated list of `T`.
+fn parse_options<T: Parse>(attrs: &[Attribute], name: &str) -> Result<Vec<T>> {
+    let mut options = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        options.extend(attr.parse_args_with(Punctuated::<T, Comma>::parse_terminated)?);
+    }
+    Ok(options)
+}
+
+/// Stores `value` in `slot`, fails if `key` was already set before.
+fn set_once<T>(slot: &mut Option<T>, key: &Ident, value: T) -> Result<()> {
+    if slot.is_some() {
+        return Err(Error::new_spanned(
+            key,
+            format!("duplicate attribute `{}`", key),
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// Diffs of `derive_patch::diff` whose only generic argument is the field
+/// type.
+const GENERIC_DIFFS: &[&str] = &["CopyDiff", "NumericDistanceDiff"];
+
+/// True if `ty` is one of the `GENERIC_DIFFS` without generic arguments.
+fn is_generic_diff(ty: &syn::TypePath) -> bool {
+    ty.qself.is_none()
+        && ty.path.segments.last().is_some_and(|segment| {
+            matches!(segment.arguments, PathArguments::None)
+                && GENERIC_DIFFS.iter().any(|diff| segment.ident == diff)
+        })
+}
@@ -0,0 +1,100 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! The where-clause of the generated types and their impls.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{punctuated::Punctuated, token::Comma, Generics, Ident, WherePredicate};
+
+/// Collects the bounds the generated code needs on the generic parameters of
+/// the original type.
+pub(crate) struct Bounds<'a> {
+    generics: &'a Generics,
+    type_params: Vec<&'a Ident>,
+    /// Set via `bound = "..."`, replaces all inferred bounds.
+    custom: Option<&'a Punctuated<WherePredicate, Comma>>,
+    inferred: Vec<TokenStream>,
+}
+
+impl<'a> Bounds<'a> {
+    pub(crate) fn new(
+        generics: &'a Generics,
+        custom: Option<&'a Punctuated<WherePredicate, Comma>>,
+    ) -> Bounds<'a> {
+        Bounds {
+            generics,
+            type_params: generics.type_params().map(|param| &param.ident).collect(),
+            custom,
+            inferred: Vec::new(),
+        }
+    }
+
+    /// True if `ty` mentions one of the generic type parameters.
+    pub(crate) fn is_generic<T: ToTokens>(&self, ty: &T) -> bool {
+        !self.type_params.is_empty() && mentions(ty.to_token_stream(), &self.type_params)
+    }
+
+    /// Requires `ty: bound`, if `ty` is generic.
+    ///
+    /// Bounds on types without generic parameters are either always true or
+    /// fail at the place the trait is used.
+    pub(crate) fn add<T: ToTokens>(&mut self, ty: &T, bound: TokenStream) {
+        if self.is_generic(ty) {
+            self.inferred.push(quote!(#ty: #bound));
+        }
+    }
+
+    /// Requires `ty: bound` as a higher-ranked bound, if `ty` is not generic.
+    ///
+    /// The bound is not checked where the impls are defined, it only hides
+    /// the errors of the generated code if it does not hold. Something else
+    /// has to report that, e.g. an assertion with a span of its own.
+    pub(crate) fn add_deferred<T: ToTokens>(&mut self, ty: &T, bound: TokenStream) {
+        if !self.is_generic(ty) {
+            self.inferred.push(quote!(for<'__deferred> #ty: #bound));
+        }
+    }
+
+    /// The where-clause of the original type plus the needed bounds.
+    pub(crate) fn where_clause(&self) -> TokenStream {
+        self.where_clause_with(None)
+    }
+
+    /// Like `where_clause`, with additional predicates for a single impl.
+    pub(crate) fn where_clause_with<I>(&self, additional: I) -> TokenStream
+    where
+        I: IntoIterator<Item = TokenStream>,
+    {
+        let original = self
+            .generics
+            .where_clause
+            .iter()
+            .flat_map(|clause| clause.predicates.iter())
+            .map(ToTokens::to_token_stream);
+        let mut predicates: Vec<TokenStream> = match self.custom {
+            Some(custom) => original
+                .chain(custom.iter().map(ToTokens::to_token_stream))
+                .collect(),
+            None => original.chain(self.inferred.iter().cloned()).collect(),
+        };
+        predicates.extend(additional);
+        if predicates.is_empty() {
+            quote!()
+        } else {
+            quote!(where #(#predicates,)*)
+        }
+    }
+}
+
+/// True if `tokens` contain one of `idents`.
+fn mentions(tokens: TokenStream, idents: &[&Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => idents.iter().any(|param| **param == ident),
+        TokenTree::Group(group) => mentions(group.stream(), idents),
+        _ => false,
+    })
+}
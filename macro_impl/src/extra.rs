@@ -0,0 +1,95 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Code generation for the `extra(...)` fields of patches and partials.
+
+use crate::attrs::ExtraField;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, Error, Member, Result};
+
+/// The `extra(...)` fields of a generated type.
+pub(crate) struct ExtraFields<'a> {
+    fields: &'a [ExtraField],
+}
+
+impl<'a> ExtraFields<'a> {
+    /// Fails if an extra field has the same name as one of the `existing`
+    /// fields, or if the generated type is a tuple struct.
+    pub(crate) fn new<'b, I>(
+        fields: &'a [ExtraField],
+        is_tuple: bool,
+        existing: I,
+    ) -> Result<ExtraFields<'a>>
+    where
+        I: IntoIterator<Item = &'b Member>,
+    {
+        if let (true, Some(field)) = (is_tuple, fields.first()) {
+            return Err(Error::new_spanned(
+                &field.ident,
+                "extra fields are not supported on tuple structs",
+            ));
+        }
+        let existing: Vec<&Member> = existing.into_iter().collect();
+        for (i, field) in fields.iter().enumerate() {
+            let duplicate = existing
+                .iter()
+                .any(|member| matches!(member, Member::Named(ident) if *ident == field.ident))
+                || fields[..i].iter().any(|other| other.ident == field.ident);
+            if duplicate {
+                return Err(Error::new_spanned(
+                    &field.ident,
+                    format!("duplicate field `{}`", field.ident),
+                ));
+            }
+        }
+        Ok(ExtraFields { fields })
+    }
+
+    /// The field declarations, for the body of the generated struct.
+    pub(crate) fn declarations(&self) -> TokenStream {
+        let idents = self.fields.iter().map(|f| &f.ident);
+        let types = self.fields.iter().map(|f| &f.ty);
+        quote!(#(#idents: #types,)*)
+    }
+
+    /// The initial values, for the constructors of the generated struct.
+    pub(crate) fn initializers(&self) -> TokenStream {
+        let idents = self.fields.iter().map(|f| &f.ident);
+        let values = self.fields.iter().map(ExtraField::init_value);
+        quote!(#(#idents: #values,)*)
+    }
+
+    /// The values of `self`, for a copy of the generated struct.
+    pub(crate) fn clones(&self) -> TokenStream {
+        let idents = self.fields.iter().map(|f| &f.ident);
+        let values = idents.clone();
+        quote!(#(#idents: ::std::clone::Clone::clone(&self.#values),)*)
+    }
+
+    /// A getter and a setter for every field.
+    pub(crate) fn accessors(&self) -> TokenStream {
+        let accessors = self.fields.iter().map(|f| {
+            let ident = &f.ident;
+            let ty = &f.ty;
+            let setter = format_ident!("set_{}", ident.unraw());
+            let get_doc = format!("Returns the extra field `{}`.", ident.unraw());
+            let set_doc = format!("Sets the extra field `{}`.", ident.unraw());
+            quote! {
+                #[doc = #get_doc]
+                pub fn #ident(&self) -> &#ty {
+                    &self.#ident
+                }
+
+                #[doc = #set_doc]
+                pub fn #setter(&mut self, value: #ty) {
+                    self.#ident = value;
+                }
+            }
+        });
+        quote!(#(#accessors)*)
+    }
+}
@@ -0,0 +1,221 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Helpers to inspect the item a derive is applied to.
+
+use crate::attrs::reject_cfg;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, punctuated::Punctuated, token::Comma, Data, DataEnum, DeriveInput, Error, Field,
+    Fields, Ident, Index, Member, Result,
+};
+
+/// The fields of a struct the derive is applied to.
+pub(crate) struct StructFields<'a> {
+    pub(crate) fields: &'a Punctuated<Field, Comma>,
+    /// True for tuple structs, the generated types are tuple structs as well.
+    pub(crate) is_tuple: bool,
+}
+
+/// Returns the fields of `input`, which has to be a struct with named fields
+/// or a tuple struct. Enums are handled before.
+///
+/// `derive` is the name of the derive, used for the error message.
+pub(crate) fn struct_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<StructFields<'a>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(StructFields {
+                fields: &fields.named,
+                is_tuple: false,
+            }),
+            Fields::Unnamed(fields) => Ok(StructFields {
+                fields: &fields.unnamed,
+                is_tuple: true,
+            }),
+            Fields::Unit => Err(Error::new_spanned(
+                input,
+                format!("`{}` can not be derived for structs without fields", derive),
+            )),
+        },
+        Data::Union(data) => Err(Error::new_spanned(
+            data.union_token,
+            format!("`{}` can not be derived for unions", derive),
+        )),
+        Data::Enum(_) => unreachable!("enums are derived by partial_enum and patch_enum"),
+    }
+}
+
+/// How the field at `index` is accessed, its name or its position.
+pub(crate) fn member(index: usize, field: &Field) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
+/// The name of `member` in docs and mismatch errors.
+pub(crate) fn member_name(member: &Member) -> String {
+    match member {
+        Member::Named(ident) => ident.unraw().to_string(),
+        Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+/// A field of a generated struct.
+pub(crate) struct Declaration {
+    /// The member of the field in the generated struct.
+    pub(crate) member: Member,
+    /// Attributes and visibility of the field.
+    pub(crate) attrs: TokenStream,
+    pub(crate) ty: TokenStream,
+}
+
+/// The body of a generated struct with `fields`, including the
+/// `where_clause`.
+///
+/// Tuple structs take their fields in order of their member, `extra` fields
+/// are only supported on structs with named fields.
+pub(crate) fn struct_body(
+    is_tuple: bool,
+    fields: &[Declaration],
+    where_clause: &TokenStream,
+    extra: TokenStream,
+) -> TokenStream {
+    let attrs = fields.iter().map(|f| &f.attrs);
+    let types = fields.iter().map(|f| &f.ty);
+    if is_tuple {
+        quote!((#(#attrs #types,)*) #where_clause;)
+    } else {
+        let members = fields.iter().map(|f| &f.member);
+        quote!(#where_clause { #(#attrs #members: #types,)* #extra })
+    }
+}
+
+/// A variant of an enum the derive is applied to.
+pub(crate) struct Variant<'a> {
+    pub(crate) ident: &'a Ident,
+    pub(crate) fields: Vec<&'a Field>,
+    /// False for variants with braces, true for `Variant` without any.
+    pub(crate) is_unit: bool,
+}
+
+/// Returns the variants of the enum `data`, which may only have variants with
+/// named fields or unit variants.
+///
+/// `derive` is the name of the derive, used for the error message.
+///
+/// `#[cfg(...)]` on variants and their fields is not supported.
+pub(crate) fn variants<'a>(data: &'a DataEnum, derive: &str) -> Result<Vec<Variant<'a>>> {
+    for variant in &data.variants {
+        reject_cfg(&variant.attrs, "enum variants")?;
+        for field in &variant.fields {
+            reject_cfg(&field.attrs, "fields of enums")?;
+        }
+    }
+    data.variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            Fields::Named(fields) => Ok(Variant {
+                ident: &variant.ident,
+                fields: fields.named.iter().collect(),
+                is_unit: false,
+            }),
+            Fields::Unit => Ok(Variant {
+                ident: &variant.ident,
+                fields: Vec::new(),
+                is_unit: true,
+            }),
+            Fields::Unnamed(_) => Err(Error::new_spanned(
+                variant,
+                format!(
+                    "`{}` can only be derived for enums with named or unit variants",
+                    derive
+                ),
+            )),
+        })
+        .collect()
+}
+
+/// The name a field is bound to in a pattern, `prefix_field`.
+///
+/// Patterns that destructure two objects at once need different names for
+/// the fields of each.
+pub(crate) fn binding(prefix: &str, field: &Ident) -> Ident {
+    format_ident!("{}_{}", prefix, field.unraw())
+}
+
+/// Joins the given conditions with `&&`, an empty list is always `true`.
+pub(crate) fn chain_or_true<I>(conditions: I) -> TokenStream
+where
+    I: Iterator<Item = TokenStream>,
+{
+    let conditions: Vec<TokenStream> = conditions.collect();
+    if conditions.is_empty() {
+        quote!(true)
+    } else {
+        quote!(#(#conditions)&&*)
+    }
+}
+
+/// Adds up the given terms with `+`, an empty list is `0`.
+pub(crate) fn sum_or_zero<I>(terms: I) -> TokenStream
+where
+    I: Iterator<Item = TokenStream>,
+{
+    let terms: Vec<TokenStream> = terms.collect();
+    if terms.is_empty() {
+        quote!(0)
+    } else {
+        quote!(#(#terms)+*)
+    }
+}
+
+/// Like `chain_or_true`, for conditions that only exist if the `#[cfg(...)]`
+/// attributes paired with them are enabled.
+pub(crate) fn cfg_chain_or_true<I>(conditions: I) -> TokenStream
+where
+    I: Iterator<Item = (TokenStream, TokenStream)>,
+{
+    let (cfgs, conditions): (Vec<TokenStream>, Vec<TokenStream>) = conditions.unzip();
+    if cfgs.iter().all(TokenStream::is_empty) {
+        return chain_or_true(conditions.into_iter());
+    }
+    quote! {{
+        let mut all = true;
+        #(
+            #cfgs
+            {
+                all = all && #conditions;
+            }
+        )*
+        all
+    }}
+}
+
+/// Like `sum_or_zero`, for terms that only exist if the `#[cfg(...)]`
+/// attributes paired with them are enabled.
+///
+/// Usable in constants.
+pub(crate) fn cfg_sum_or_zero<I>(terms: I) -> TokenStream
+where
+    I: Iterator<Item = (TokenStream, TokenStream)>,
+{
+    let (cfgs, terms): (Vec<TokenStream>, Vec<TokenStream>) = terms.unzip();
+    if cfgs.iter().all(TokenStream::is_empty) {
+        return sum_or_zero(terms.into_iter());
+    }
+    quote! {{
+        let mut sum = 0;
+        #(
+            #cfgs
+            {
+                sum += #terms;
+            }
+        )*
+        sum
+    }}
+}
@@ -9,16 +9,194 @@
 
 #![deny(missing_docs)]
 
+extern crate proc_macro;
+
+mod attrs;
+mod bounds;
+mod extra;
+mod input;
+mod partial;
+mod partial_enum;
+mod patch;
+mod patch_enum;
+
+#[cfg(test)]
+mod snapshots;
+
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
 
-/// todo:
-#[proc_macro_derive(Partial)]
+/// Generates `Partial<Name>`, a variant of the struct where every field is
+/// optional.
+///
+/// # Field attributes
+/// - `#[partial(skip)]`: the field is not part of the partial. `build()` and
+///   `TryFrom<Partial>` fill it with `Default::default()`.
+/// - `#[partial(skip, default = expr)]`: like `skip`, but uses `expr` as the
+///   value of the field.
+/// - `#[partial(force)]`: the field is always present in the partial instead
+///   of being optional. It has to be passed to `new` and `with_arguments` and
+///   does not count towards `count()` and `MAX_FIELDS`.
+/// - `#[partial(force, default = expr)]`: like `force`, uses `expr` for the
+///   field in `Default::default()` of the partial. `default` without an
+///   expression uses `Default::default()`. The partial only implements
+///   `Default` if every forced field has a default.
+/// - `#[partial(nested)]`: the field type derives `Partial` itself. The
+///   partial stores the partial of that type, so it can hold only some of its
+///   fields. `merge_into`, `apply`, `count`, `build` and `MAX_FIELDS` work on
+///   the fields of the nested partial.
+///
+/// # Struct attributes
+/// - `#[partial(extra(name: Type, other: Type = expr))]`: fields that only
+///   exist on the partial, e.g. where the data came from. They are
+///   initialised with `expr` or `Default::default()` and accessed via
+///   `name()` and `set_name(value)`. They do not influence `apply`, `build`
+///   and the other operations on the partial.
+/// - `#[partial(bound = "T: Trait, ...")]`: replaces the bounds the derive
+///   adds to the generic parameters, see "Generics" below.
+/// - `#[partial(name = "Name")]`: the name of the partial, defaults to
+///   `Partial<Name>`. `#[derive(Patch)]` reads it to find the partial.
+/// - `#[partial(vis = "pub(crate)")]`: the visibility of the partial, defaults
+///   to the one of the struct. The partial and the struct name each other in
+///   trait impls, so neither may be private to the other.
+/// - `#[partial(derive(Hash, Eq))]`: derives in addition to `PartialEq`,
+///   `Clone` and `Debug`.
+/// - `#[partial(attr(...))]`: adds `#[...]` to the partial, e.g.
+///   `attr(serde(rename_all = "camelCase"))`.
+/// - `#[partial(forward_attrs(doc, serde))]`: the attributes of the fields
+///   that are copied to the fields of the partial, defaults to `doc` and
+///   `serde`, so the partial serializes like the struct. `cfg_attr` is copied
+///   if all the attributes it adds are in the list.
+///
+/// `#[cfg(...)]` on a field is always copied and removes the field from every
+/// part of the partial, including `new`, `with_arguments` and `MAX_FIELDS`. It
+/// is not supported on fields of tuple structs and enums.
+///
+/// The struct itself implements `PartialExt`, the partial implements
+/// `traits::Partial` and `From<Name>`.
+///
+/// # Tuple structs
+/// The partial of a tuple struct is a tuple struct as well, e.g.
+/// `PartialPair(pub Option<u32>, pub Option<String>)` for
+/// `struct Pair(u32, String)`. Its fields are numbered in order, skipped
+/// fields are left out. `new` and `with_arguments` take the fields in the same
+/// order. `extra` is not supported.
+///
+/// # Enums
+/// For an enum, the partial is an enum with the same variants, each holding
+/// the optional fields of its variant. Applying it to an object of another
+/// variant replaces the object, if the partial is complete. `skip` works like
+/// on structs, `force`, `nested` and `extra` are not supported.
+///
+/// # Generics
+/// Generic parameters, lifetimes and the where-clause of the struct are
+/// copied to the partial. Every field whose type mentions a type parameter
+/// gets the bounds the partial needs, e.g. `T: Clone + PartialEq +
+/// PatchableField`. Use `bound` if these bounds are too strict.
+///
+/// Have a look at tests/template_partial.rs for the generated code.
+#[proc_macro_derive(Partial, attributes(partial))]
 pub fn partial(input: TokenStream) -> TokenStream {
-    input
+    let input = parse_macro_input!(input as DeriveInput);
+    partial::derive(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
 }
 
-/// todo:
-#[proc_macro_derive(Patch)]
+/// Generates `<Name>Patch`, which stores a `Diff` for every field of the
+/// struct.
+///
+/// The generated patch can be created out of the `Partial<Name>` of the same
+/// struct, so `#[derive(Partial)]` is required as well.
+///
+/// The struct itself implements `PatchableExt`, which creates a patch out of
+/// two objects, and `PatchableField`.
+///
+/// The patch implements `InvertPatch` if the diffs of all fields implement
+/// `diff::Invert`. `InvertPatch::invert` returns the patch that undoes the
+/// patch, by inverting the diff of every field. Id and extra fields are kept.
+/// `Patch::coalesce` merges a following patch of the same fields into the
+/// patch, see `history::UndoStack`.
+///
+/// `Patch::merge_into` combines two consecutive patches field by field with
+/// `Diff::merge`, `Patch::overwrite_into` replaces the fields of another patch
+/// and `Patch::rebase` takes the old values from the current object, see
+/// `Diff::rebase`. Nested patches are merged and rebased field by field too.
+///
+/// `Patch::merge_concurrent` merges two patches of the same object. Fields
+/// that both patches set are resolved by a `conflict::ConflictResolver`,
+/// e.g. `conflict::OursWins`, or combined with `Diff::combine`. Nested
+/// patches are merged field by field, their conflicts are named with the
+/// path to the field, e.g. `address.city`.
+///
+/// # Tuple structs
+/// The patch of a tuple struct is a tuple struct as well, with the fields
+/// numbered in order and skipped fields left out. Mismatches are reported
+/// with the position of the field, e.g. `0`. `extra` is not supported.
+///
+/// The patch of a newtype, e.g. `struct Meters(f64)`, is a `Diff` of the
+/// newtype that forwards to the diff of the wrapped field. Use it as the diff
+/// of fields of the newtype, e.g. `#[patch(diff = MetersPatch)]`, or create it
+/// out of the diff of the wrapped field with `From`.
+///
+/// # Enums
+/// For an enum, the patch is an enum with a variant per variant of the enum,
+/// holding the diffs of its fields, and a `Replace` variant, which replaces
+/// the whole object with a `CopyDiff` if the variant changes. Applying a
+/// patch to an object of another variant fails with
+/// `MismatchType::VariantMismatch`. `new_with_partial` returns an
+/// `IncompleteError` if the partial is another variant and not complete.
+/// `merge_into` fails with `MismatchType::MergeConflict` if a `Replace`
+/// follows a patch of the fields, the value before it is not known.
+/// `id` and `extra` are not supported, and no variant may be called
+/// `Replace`.
+///
+/// # Field attributes
+/// - `#[patch(diff = Type)]`: the `Diff` that stores the changes of this
+///   field, defaults to `CopyDiff`. The `Object` of the diff has to be the
+///   field type. `CopyDiff` and `NumericDistanceDiff` can be written without
+///   generic arguments, the field type is filled in. `VecDiff<T>` stores the
+///   changes of a `Vec<T>` as an edit script, `TextDiff` the changes of a
+///   `String` as hunks, `MapDiff<K, V>` the changes of a map per key and
+///   `SetDiff<T>` the added and removed elements of a set.
+/// - `#[patch(id)]`: the field identifies the patched object. It is copied
+///   into the patch instead of being diffed and has to match the object the
+///   patch is applied to, see `Patch::is_correct_target`. Put it on several
+///   fields for a composite key.
+/// - `#[patch(skip)]`: the field is not part of the patch.
+/// - `#[patch(nested)]`: the field type derives `Patch` itself. The field is
+///   stored as a `NestedPatchDiff`, so only the changed fields of the nested
+///   struct are part of the patch. Mismatches in it are reported with a dotted
+///   path, e.g. `address.city`.
+///
+/// # Struct attributes
+/// - `#[patch(extra(name: Type, other: Type = expr))]`: fields that only exist
+///   on the patch, e.g. a timestamp or a patch id. They are initialised with
+///   `expr` or `Default::default()` and accessed via `name()` and
+///   `set_name(value)`. They are serialized with the patch, but do not
+///   influence `apply`.
+/// - `#[patch(bound = "T: Trait, ...")]`: replaces the bounds the derive adds
+///   to the generic parameters, see "Generics" below.
+/// - `#[patch(name = "Name")]`, `#[patch(vis = "pub(crate)")]`,
+///   `#[patch(derive(Hash))]` and `#[patch(attr(...))]`: the name, visibility,
+///   additional derives and attributes of the patch, like for `Partial`.
+/// - `#[patch(forward_attrs(doc, serde))]`: the attributes of the fields that
+///   are copied to the patch, like for `Partial`.
+///
+/// `#[cfg(...)]` on a field removes it from the patch, like for `Partial`.
+///
+/// # Generics
+/// Generic parameters, lifetimes and the where-clause of the struct are
+/// copied to the patch. Every field whose type mentions a type parameter gets
+/// the bounds the patch needs, e.g. `T: PatchableField` for a `CopyDiff` or
+/// `Diff<Object = T>` for a custom diff. Use `bound` if these bounds are too
+/// strict or can not be inferred.
+///
+/// Have a look at tests/template_patch.rs for the generated code.
+#[proc_macro_derive(Patch, attributes(patch))]
 pub fn patch(input: TokenStream) -> TokenStream {
-    input
+    let input = parse_macro_input!(input as DeriveInput);
+    patch::derive(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
 }
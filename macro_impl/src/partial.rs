@@ -0,0 +1,628 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Code generation for `#[derive(Partial)]`.
+
+use crate::{
+    attrs::{cfg_attrs, reject_cfg, PartialFieldAttrs, PatchFieldAttrs, StructAttrs},
+    bounds::Bounds,
+    extra::ExtraFields,
+    input::{
+        cfg_chain_or_true, cfg_sum_or_zero, member, member_name, struct_body, struct_fields,
+        Declaration, StructFields,
+    },
+    partial_enum,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, Attribute, Data, DeriveInput, Field, Ident, Index, Member, Result, Type};
+
+/// A field of the struct the partial is generated for.
+struct PartialField<'a> {
+    /// The field in the original struct.
+    member: Member,
+    /// The field in the partial, differs from `member` for tuple structs
+    /// with skipped fields.
+    slot: Member,
+    /// The name of the field in docs.
+    name: String,
+    /// The argument for the field in `new` and `with_arguments`.
+    param: Ident,
+    ty: &'a Type,
+    attrs: PartialFieldAttrs,
+    /// Set via `#[patch(id)]`
+    is_id: bool,
+    /// The attributes of the field in the original struct.
+    field_attrs: &'a [Attribute],
+    /// The `#[cfg(...)]` attributes of the field, put on all code for it.
+    cfg: TokenStream,
+}
+
+impl<'a> PartialField<'a> {
+    fn new(index: usize, field: &'a Field) -> Result<PartialField<'a>> {
+        let member = member(index, field);
+        let param = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("field_{}", index),
+        };
+        Ok(PartialField {
+            slot: member.clone(),
+            name: member_name(&member),
+            member,
+            param,
+            ty: &field.ty,
+            attrs: PartialFieldAttrs::parse(&field.attrs)?,
+            // malformed `#[patch]` attributes are reported by the Patch derive
+            is_id: PatchFieldAttrs::parse(&field.attrs).is_ok_and(|attrs| attrs.id.is_some()),
+            field_attrs: &field.attrs,
+            cfg: cfg_attrs(&field.attrs),
+        })
+    }
+
+    /// `statement`, only if the field exists.
+    fn with_cfg(&self, statement: TokenStream) -> TokenStream {
+        if self.cfg.is_empty() {
+            statement
+        } else {
+            let cfg = &self.cfg;
+            quote!(#cfg { #statement })
+        }
+    }
+
+    /// Adds the bounds the partial needs for this field.
+    fn add_bounds(&self, bounds: &mut Bounds) {
+        if self.attrs.skip.is_some() {
+            if !matches!(self.attrs.default, Some((_, Some(_)))) {
+                bounds.add(self.ty, quote!(::std::default::Default));
+            }
+            return;
+        }
+        if self.attrs.nested.is_some() {
+            bounds.add(self.ty, quote!(::derive_patch::traits::PartialExt));
+            bounds.add(&self.stored_type(), quote!(::std::clone::Clone));
+        } else {
+            bounds.add(
+                self.ty,
+                quote!(
+                    ::std::clone::Clone
+                        + ::std::cmp::PartialEq
+                        + ::derive_patch::patchable::PatchableField
+                ),
+            );
+        }
+        if self.is_id {
+            bounds.add(self.ty, quote!(::std::fmt::Debug));
+        }
+    }
+
+    /// The type stored in the `Option` of the partial.
+    ///
+    /// `nested` fields store the partial of their type.
+    fn stored_type(&self) -> TokenStream {
+        let ty = self.ty;
+        if self.attrs.nested.is_some() {
+            quote!(<#ty as ::derive_patch::traits::PartialExt>::Partial)
+        } else {
+            quote!(#ty)
+        }
+    }
+
+    /// The `Base<()>` implementation of a `nested` field.
+    fn nested_base(&self) -> TokenStream {
+        let stored = self.stored_type();
+        quote!(<#stored as ::derive_patch::traits::Base<()>>)
+    }
+
+    /// The `MAX_FIELDS` this field adds to the partial.
+    fn max_fields(&self) -> TokenStream {
+        if self.attrs.nested.is_some() {
+            let base = self.nested_base();
+            quote!(#base::MAX_FIELDS)
+        } else {
+            quote!(1)
+        }
+    }
+
+    /// True if the field is set, for `is_complete`.
+    fn is_complete(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            let base = self.nested_base();
+            quote!(self.#slot.as_ref().map_or(false, #base::is_complete))
+        } else {
+            quote!(self.#slot.is_some())
+        }
+    }
+
+    /// True if the field is not set, for `is_empty`.
+    fn is_empty(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            let base = self.nested_base();
+            quote!(self.#slot.as_ref().map_or(true, #base::is_empty))
+        } else {
+            quote!(self.#slot.is_none())
+        }
+    }
+
+    /// The number of set fields, for `count`.
+    fn count(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            let base = self.nested_base();
+            quote!(self.#slot.as_ref().map_or(0, #base::count))
+        } else {
+            quote!(self.#slot.is_some() as u32)
+        }
+    }
+
+    /// Copies the field from `self` into the partial `obj`.
+    fn merge_into(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            quote! {
+                match (&self.#slot, &mut obj.#slot) {
+                    (::std::option::Option::Some(value), ::std::option::Option::Some(target)) => {
+                        ::derive_patch::traits::Partial::merge_into(value, target);
+                    }
+                    (::std::option::Option::Some(value), target) => {
+                        *target = ::std::option::Option::Some(value.clone());
+                    }
+                    (::std::option::Option::None, _) => {}
+                }
+            }
+        } else {
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#slot {
+                    obj.#slot = ::std::option::Option::Some(value.clone());
+                }
+            }
+        }
+    }
+
+    /// Compares the field with the one of the partial `obj`, if both are set.
+    fn is_partial_equal_existing(&self) -> TokenStream {
+        let slot = &self.slot;
+        let equal = if self.attrs.nested.is_some() {
+            quote!(::derive_patch::traits::Partial::is_partial_equal_existing(
+                lhs, rhs
+            ))
+        } else {
+            quote!(::derive_patch::patchable::PatchableField::compare(lhs, rhs))
+        };
+        quote! {
+            if let (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) =
+                (&self.#slot, &obj.#slot)
+            {
+                if !#equal {
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// The value of the field in `build`, returns `Err(())` if it is not set.
+    fn build(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            quote!(::derive_patch::traits::Partial::build(self.#slot.as_ref().ok_or(())?)?)
+        } else {
+            quote!(self.#slot.as_ref().ok_or(())?.clone())
+        }
+    }
+
+    /// The value of the field when converting a complete partial `value`.
+    fn unwrap(&self) -> TokenStream {
+        let slot = &self.slot;
+        if self.attrs.nested.is_some() {
+            quote! {
+                ::derive_patch::traits::Partial::build(value.#slot.as_ref().unwrap())
+                    .ok()
+                    .unwrap()
+            }
+        } else {
+            quote!(value.#slot.unwrap())
+        }
+    }
+
+    /// Writes the field into the original struct `obj`.
+    fn apply(&self) -> TokenStream {
+        let slot = &self.slot;
+        let member = &self.member;
+        if self.attrs.nested.is_some() {
+            let base = self.nested_base();
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#slot {
+                    #base::apply(value, &mut obj.#member);
+                }
+            }
+        } else {
+            quote! {
+                if let ::std::option::Option::Some(value) = &self.#slot {
+                    obj.#member = value.clone();
+                }
+            }
+        }
+    }
+
+    /// The partial value of the field out of the original struct `value`.
+    fn value_of_original(&self) -> TokenStream {
+        let member = &self.member;
+        if self.attrs.nested.is_some() {
+            quote!(::std::option::Option::Some(::std::convert::From::from(value.#member)))
+        } else {
+            quote!(::std::option::Option::Some(value.#member))
+        }
+    }
+}
+
+/// Adds the bounds the partial of a struct with `fields` needs.
+///
+/// Code that uses the partial, e.g. the patch, needs the same bounds.
+pub(crate) fn add_bounds<'a, I>(bounds: &mut Bounds, fields: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a Field>,
+{
+    for (index, field) in fields.into_iter().enumerate() {
+        PartialField::new(index, field)?.add_bounds(bounds);
+    }
+    Ok(())
+}
+
+/// Generates the `Partial<Name>` struct and all its impls for `input`.
+///
+/// The partial of a tuple struct is a tuple struct as well, its fields are
+/// numbered in order, leaving out the skipped ones.
+pub(crate) fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    if let Data::Enum(data) = &input.data {
+        return partial_enum::derive(input, data);
+    }
+
+    let StructFields { fields, is_tuple } = struct_fields(input, "Partial")?;
+    let mut fields = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| PartialField::new(index, field))
+        .collect::<Result<Vec<_>>>()?;
+    if is_tuple {
+        for f in &fields {
+            reject_cfg(f.field_attrs, "fields of tuple structs")?;
+        }
+        let present = fields.iter_mut().filter(|f| f.attrs.skip.is_none());
+        for (index, field) in present.enumerate() {
+            field.slot = Member::Unnamed(Index::from(index));
+        }
+    }
+
+    let name = &input.ident;
+    let struct_attrs = StructAttrs::parse(&input.attrs, "partial")?;
+    let vis = struct_attrs.vis(&input.vis);
+    let partial_name = struct_attrs.name(format_ident!("Partial{}", name));
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let name_ty = quote!(#name #ty_generics);
+    let partial_ty = quote!(#partial_name #ty_generics);
+
+    let (skipped, present): (Vec<&PartialField>, Vec<&PartialField>) =
+        fields.iter().partition(|f| f.attrs.skip.is_some());
+    let (forced, optional): (Vec<&PartialField>, Vec<&PartialField>) =
+        present.iter().partition(|f| f.attrs.force.is_some());
+
+    let slots: Vec<&Member> = optional.iter().map(|f| &f.slot).collect();
+    let members: Vec<&Member> = optional.iter().map(|f| &f.member).collect();
+    let params: Vec<&Ident> = optional.iter().map(|f| &f.param).collect();
+    let types: Vec<TokenStream> = optional.iter().map(|f| f.stored_type()).collect();
+    let cfgs: Vec<&TokenStream> = optional.iter().map(|f| &f.cfg).collect();
+    let extra = ExtraFields::new(
+        &struct_attrs.extra,
+        is_tuple,
+        fields.iter().map(|f| &f.member),
+    )?;
+    let extra_fields = extra.declarations();
+    let extra_init = extra.initializers();
+    let extra_accessors = extra.accessors();
+
+    let mut bounds = Bounds::new(generics, struct_attrs.bound.as_ref());
+    for f in &fields {
+        f.add_bounds(&mut bounds);
+    }
+    let where_clause = bounds.where_clause();
+
+    let forced_slots: Vec<&Member> = forced.iter().map(|f| &f.slot).collect();
+    let forced_members: Vec<&Member> = forced.iter().map(|f| &f.member).collect();
+    let forced_params: Vec<&Ident> = forced.iter().map(|f| &f.param).collect();
+    let forced_types: Vec<&Type> = forced.iter().map(|f| f.ty).collect();
+    let forced_cfgs: Vec<&TokenStream> = forced.iter().map(|f| &f.cfg).collect();
+    let forced_merge_into: Vec<TokenStream> = forced
+        .iter()
+        .map(|f| {
+            let slot = &f.slot;
+            f.with_cfg(quote!(obj.#slot = self.#slot.clone();))
+        })
+        .collect();
+    let forced_is_partial_equal_existing: Vec<TokenStream> = forced
+        .iter()
+        .map(|f| {
+            let slot = &f.slot;
+            f.with_cfg(quote! {
+                if !::derive_patch::patchable::PatchableField::compare(&self.#slot, &obj.#slot) {
+                    return false;
+                }
+            })
+        })
+        .collect();
+    let forced_apply: Vec<TokenStream> = forced
+        .iter()
+        .map(|f| {
+            let (slot, member) = (&f.slot, &f.member);
+            f.with_cfg(quote!(obj.#member = self.#slot.clone();))
+        })
+        .collect();
+    let skipped_members: Vec<&Member> = skipped.iter().map(|f| &f.member).collect();
+    let skipped_cfgs: Vec<&TokenStream> = skipped.iter().map(|f| &f.cfg).collect();
+    let skipped_defaults: Vec<TokenStream> = skipped
+        .iter()
+        .map(|f| f.attrs.default_value(f.ty))
+        .collect();
+    // the generic arguments of `with_arguments` must not shadow the ones of
+    // the struct
+    let type_params: Vec<String> = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect();
+    let generic_args: Vec<Ident> = optional
+        .iter()
+        .map(|f| {
+            let mut arg = match &f.member {
+                Member::Named(ident) => ident.unraw().to_string().to_uppercase(),
+                Member::Unnamed(index) => format!("F{}", index.index),
+            };
+            while type_params.contains(&arg) {
+                arg.push('_');
+            }
+            format_ident!("{}", arg)
+        })
+        .collect();
+    // named partials declare the forced fields first, tuple partials keep
+    // the order of the struct
+    let declared: Vec<&PartialField> = if is_tuple {
+        present.clone()
+    } else {
+        forced.iter().chain(&optional).copied().collect()
+    };
+    let declarations: Vec<Declaration> = declared
+        .iter()
+        .map(|f| {
+            let forwarded = struct_attrs.forwarded(f.field_attrs);
+            let cfg = &f.cfg;
+            if f.attrs.force.is_some() {
+                let doc = format!("Value for `{}::{}`, always present.", name, f.name);
+                let ty = f.ty;
+                Declaration {
+                    member: f.slot.clone(),
+                    attrs: quote!(#[doc = #doc] #forwarded #cfg pub),
+                    ty: quote!(#ty),
+                }
+            } else {
+                let doc = format!("Optional value for `{}::{}`.", name, f.name);
+                let ty = f.stored_type();
+                Declaration {
+                    member: f.slot.clone(),
+                    attrs: quote!(#[doc = #doc] #forwarded #cfg pub),
+                    ty: quote!(::std::option::Option<#ty>),
+                }
+            }
+        })
+        .collect();
+    let body = struct_body(is_tuple, &declarations, &where_clause, extra_fields);
+    let max_fields = cfg_sum_or_zero(optional.iter().map(|f| (f.cfg.clone(), f.max_fields())));
+
+    let is_complete = cfg_chain_or_true(optional.iter().map(|f| (f.cfg.clone(), f.is_complete())));
+    let is_empty = cfg_chain_or_true(optional.iter().map(|f| (f.cfg.clone(), f.is_empty())));
+    let count = cfg_sum_or_zero(optional.iter().map(|f| (f.cfg.clone(), f.count())));
+    let merge_into: Vec<TokenStream> = optional
+        .iter()
+        .map(|f| f.with_cfg(f.merge_into()))
+        .collect();
+    let is_partial_equal_existing: Vec<TokenStream> = optional
+        .iter()
+        .map(|f| f.with_cfg(f.is_partial_equal_existing()))
+        .collect();
+    let build: Vec<TokenStream> = optional.iter().map(|f| f.build()).collect();
+    let unwrap: Vec<TokenStream> = optional.iter().map(|f| f.unwrap()).collect();
+    let apply: Vec<TokenStream> = optional.iter().map(|f| f.with_cfg(f.apply())).collect();
+    let from_value: Vec<TokenStream> = optional.iter().map(|f| f.value_of_original()).collect();
+
+    // a partial can only be `Default` if all forced fields have a default
+    let default_impl = forced
+        .iter()
+        .map(|f| f.attrs.forced_default(f.ty))
+        .collect::<Option<Vec<_>>>()
+        .map(|forced_defaults| {
+            quote! {
+                impl #impl_generics ::std::default::Default for #partial_ty #where_clause {
+                    fn default() -> Self {
+                        #partial_name {
+                            #(#forced_cfgs #forced_slots: #forced_defaults,)*
+                            #(#cfgs #slots: ::std::option::Option::None,)*
+                            #extra_init
+                        }
+                    }
+                }
+            }
+        });
+
+    let struct_doc = if forced.is_empty() {
+        format!("A `{}` where every field is optional.", name)
+    } else {
+        format!(
+            "A `{}` where every field but the forced ones is optional.",
+            name
+        )
+    };
+    let build_doc = format!(
+        "Builds a `{}` out of this partial.\n\nFails if not all fields are set, see `is_complete`.",
+        name
+    );
+    // the `#[patch(id)]` fields identify the object in an `IncompleteError`
+    let ids: Vec<&PartialField> = present
+        .iter()
+        .filter(|f| f.is_id && f.cfg.is_empty())
+        .copied()
+        .collect();
+    let object_id = if ids.is_empty() {
+        let name = name.to_string();
+        quote!(#name)
+    } else {
+        let id_slots = ids.iter().map(|f| &f.slot);
+        let id_names: Vec<String> = ids.iter().map(|f| format!("{}: {{:?}}", f.name)).collect();
+        let format = format!("{} {{{{ {} }}}}", name, id_names.join(", "));
+        quote!(::std::format!(#format, #(value.#id_slots),*))
+    };
+
+    let type_attrs = struct_attrs.type_attrs();
+
+    Ok(quote! {
+        #[doc = #struct_doc]
+        #[derive(PartialEq, Clone, Debug)]
+        #type_attrs
+        #[allow(clippy::option_option)]
+        #vis struct #partial_name #generics #body
+
+        impl #impl_generics #partial_ty #where_clause {
+            /// Generates an empty `Partial`, only the forced fields are set.
+            #[allow(clippy::new_without_default)]
+            pub fn new(#(#forced_cfgs #forced_params: #forced_types),*) -> Self {
+                #partial_name {
+                    #(#forced_cfgs #forced_slots: #forced_params,)*
+                    #(#cfgs #slots: ::std::option::Option::None,)*
+                    #extra_init
+                }
+            }
+
+            /// Generates a `Partial` with the given values.
+            ///
+            /// The forced fields come first. Every other argument accepts either
+            /// the value itself or an `Option`.
+            #[allow(clippy::too_many_arguments)]
+            pub fn with_arguments<
+                #(#cfgs #generic_args: ::std::convert::Into<::std::option::Option<#types>>),*
+            >(
+                #(#forced_cfgs #forced_params: #forced_types,)*
+                #(#cfgs #params: #generic_args),*
+            ) -> Self {
+                #partial_name {
+                    #(#forced_cfgs #forced_slots: #forced_params,)*
+                    #(#cfgs #slots: #params.into(),)*
+                    #extra_init
+                }
+            }
+
+            #extra_accessors
+
+            /// add all attributes set here in to the given Partial
+            ///
+            /// If an attribute in here is already set in `obj`, then it gets
+            /// overwritten
+            pub fn merge_into(&self, obj: &mut Self) {
+                #(#forced_merge_into)*
+                #(#merge_into)*
+            }
+
+            /// check all attributes that are present on BOTH objects, if they
+            /// are equal. If there are no attributes present in both, will
+            /// return true.
+            pub fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+                #(#forced_is_partial_equal_existing)*
+                #(#is_partial_equal_existing)*
+                true
+            }
+
+            #[doc = #build_doc]
+            #[allow(clippy::result_unit_err)]
+            pub fn build(&self) -> ::std::result::Result<#name_ty, ()> {
+                ::std::result::Result::Ok(#name {
+                    #(#forced_cfgs #forced_members: self.#forced_slots.clone(),)*
+                    #(#cfgs #members: #build,)*
+                    #(#skipped_cfgs #skipped_members: #skipped_defaults,)*
+                })
+            }
+        }
+
+        #default_impl
+
+        impl #impl_generics ::derive_patch::traits::Base<()> for #partial_ty #where_clause {
+            type Target = #name_ty;
+
+            const MAX_FIELDS: usize = #max_fields;
+
+            fn is_complete(&self) -> bool {
+                #is_complete
+            }
+
+            fn is_empty(&self) -> bool {
+                #is_empty
+            }
+
+            fn count(&self) -> u32 {
+                #count
+            }
+
+            fn apply(&self, obj: &mut #name_ty) {
+                #(#forced_apply)*
+                #(#apply)*
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Partial for #partial_ty #where_clause {
+            fn build(&self) -> ::std::result::Result<#name_ty, ()> {
+                #partial_name::build(self)
+            }
+
+            fn merge_into(&self, obj: &mut Self) {
+                #partial_name::merge_into(self, obj)
+            }
+
+            fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+                #partial_name::is_partial_equal_existing(self, obj)
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::PartialExt for #name_ty #where_clause {
+            type Partial = #partial_ty;
+        }
+
+        impl #impl_generics ::std::convert::From<#name_ty> for #partial_ty #where_clause {
+            fn from(value: #name_ty) -> Self {
+                #partial_name {
+                    #(#forced_cfgs #forced_slots: value.#forced_members,)*
+                    #(#cfgs #slots: #from_value,)*
+                    #extra_init
+                }
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<#partial_ty> for #name_ty #where_clause {
+            type Error = ::derive_patch::mismatch::IncompleteError<#partial_ty>;
+
+            fn try_from(value: #partial_ty) -> ::std::result::Result<Self, Self::Error> {
+                if <#partial_ty as ::derive_patch::traits::Base<()>>::is_complete(&value) {
+                    ::std::result::Result::Ok(#name {
+                        #(#forced_cfgs #forced_members: value.#forced_slots,)*
+                        #(#cfgs #members: #unwrap,)*
+                        #(#skipped_cfgs #skipped_members: #skipped_defaults,)*
+                    })
+                } else {
+                    ::std::result::Result::Err(::derive_patch::mismatch::IncompleteError::new(
+                        "try_from",
+                        #object_id,
+                        value,
+                    ))
+                }
+            }
+        }
+    })
+}
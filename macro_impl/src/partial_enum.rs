@@ -0,0 +1,366 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Code generation for `#[derive(Partial)]` on enums.
+
+use crate::{
+    attrs::{PartialFieldAttrs, StructAttrs},
+    bounds::Bounds,
+    input::{binding, chain_or_true, sum_or_zero, variants},
+    partial,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DataEnum, DeriveInput, Error, Ident, Result, Type};
+
+/// A variant of the enum the partial is generated for.
+struct PartialVariant<'a> {
+    ident: &'a Ident,
+    is_unit: bool,
+    /// The fields that are part of the partial.
+    optional: Vec<(&'a Ident, &'a Type)>,
+    /// The attributes copied to the `optional` fields of the partial.
+    forwarded: Vec<TokenStream>,
+    /// The fields skipped by the partial, with their default value.
+    skipped: Vec<(&'a Ident, TokenStream)>,
+}
+
+impl<'a> PartialVariant<'a> {
+    /// `Variant { field: prefix_field, .. }`, binds all optional fields.
+    fn pattern(&self, prefix: &str) -> TokenStream {
+        let ident = self.ident;
+        let fields = self.optional.iter().map(|(field, _)| field);
+        let bindings = self
+            .optional
+            .iter()
+            .map(|(field, _)| binding(prefix, field));
+        quote!(#ident { #(#fields: #bindings,)* .. })
+    }
+}
+
+/// Generates the `Partial<Name>` enum and all its impls for `input`.
+///
+/// Every variant of the partial holds the optional fields of the matching
+/// variant of the enum.
+pub(crate) fn derive(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream> {
+    let struct_attrs = StructAttrs::parse(&input.attrs, "partial")?;
+    if let Some(extra) = struct_attrs.extra.first() {
+        return Err(Error::new_spanned(
+            &extra.ident,
+            "extra fields are not supported on enums",
+        ));
+    }
+
+    let mut partial_variants = Vec::new();
+    for variant in variants(data, "Partial")? {
+        let mut optional = Vec::new();
+        let mut skipped = Vec::new();
+        let mut forwarded = Vec::new();
+        for field in &variant.fields {
+            let attrs = PartialFieldAttrs::parse(&field.attrs)?;
+            if let Some(unsupported) = attrs.force.as_ref().or(attrs.nested.as_ref()) {
+                return Err(Error::new_spanned(
+                    unsupported,
+                    format!("`{}` is not supported on enums", unsupported),
+                ));
+            }
+            let ident = field.ident.as_ref().expect("named field");
+            if attrs.skip.is_some() {
+                skipped.push((ident, attrs.default_value(&field.ty)));
+            } else {
+                optional.push((ident, &field.ty));
+                forwarded.push(struct_attrs.forwarded(&field.attrs));
+            }
+        }
+        partial_variants.push(PartialVariant {
+            ident: variant.ident,
+            is_unit: variant.is_unit,
+            optional,
+            forwarded,
+            skipped,
+        });
+    }
+
+    let name = &input.ident;
+    let vis = struct_attrs.vis(&input.vis);
+    let partial_name = struct_attrs.name(format_ident!("Partial{}", name));
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let name_ty = quote!(#name #ty_generics);
+    let partial_ty = quote!(#partial_name #ty_generics);
+
+    let mut bounds = Bounds::new(generics, struct_attrs.bound.as_ref());
+    partial::add_bounds(&mut bounds, data.variants.iter().flat_map(|v| &v.fields))?;
+    let where_clause = bounds.where_clause();
+
+    let declarations = partial_variants.iter().map(|v| {
+        let ident = v.ident;
+        let doc = format!("Optional values for `{}::{}`.", name, ident);
+        let fields = v.optional.iter().map(|(field, _)| field);
+        let types = v.optional.iter().map(|(_, ty)| ty);
+        let forwarded = &v.forwarded;
+        let field_docs = v
+            .optional
+            .iter()
+            .map(|(field, _)| format!("Optional value for `{}::{}::{}`.", name, ident, field));
+        if v.is_unit {
+            quote!(#[doc = #doc] #ident)
+        } else {
+            quote! {
+                #[doc = #doc]
+                #ident {
+                    #(
+                        #[doc = #field_docs]
+                        #forwarded
+                        #fields: ::std::option::Option<#types>,
+                    )*
+                }
+            }
+        }
+    });
+
+    let max_fields = partial_variants
+        .iter()
+        .map(|v| v.optional.len())
+        .max()
+        .unwrap_or(0);
+
+    let self_patterns: Vec<TokenStream> =
+        partial_variants.iter().map(|v| v.pattern("self")).collect();
+    let obj_patterns: Vec<TokenStream> =
+        partial_variants.iter().map(|v| v.pattern("obj")).collect();
+    let self_bindings: Vec<Vec<Ident>> = partial_variants
+        .iter()
+        .map(|v| v.optional.iter().map(|(f, _)| binding("self", f)).collect())
+        .collect();
+    let obj_bindings: Vec<Vec<Ident>> = partial_variants
+        .iter()
+        .map(|v| v.optional.iter().map(|(f, _)| binding("obj", f)).collect())
+        .collect();
+
+    let is_complete = self_bindings
+        .iter()
+        .map(|fields| chain_or_true(fields.iter().map(|f| quote!(#f.is_some()))));
+    let is_empty = self_bindings
+        .iter()
+        .map(|fields| chain_or_true(fields.iter().map(|f| quote!(#f.is_none()))));
+    let count = self_bindings
+        .iter()
+        .map(|fields| sum_or_zero(fields.iter().map(|f| quote!(#f.is_some() as u32))));
+
+    let build = partial_variants
+        .iter()
+        .zip(&self_bindings)
+        .map(|(v, bindings)| {
+            let ident = v.ident;
+            let fields = v.optional.iter().map(|(field, _)| field);
+            let skipped = v.skipped.iter().map(|(field, _)| field);
+            let defaults = v.skipped.iter().map(|(_, default)| default);
+            quote! {
+                #name::#ident {
+                    #(#fields: #bindings.as_ref().ok_or(())?.clone(),)*
+                    #(#skipped: #defaults,)*
+                }
+            }
+        });
+    let try_from = partial_variants.iter().map(|v| {
+        let ident = v.ident;
+        let fields: Vec<&Ident> = v.optional.iter().map(|(field, _)| *field).collect();
+        let bindings: Vec<Ident> = fields.iter().map(|f| binding("value", f)).collect();
+        let skipped = v.skipped.iter().map(|(field, _)| field);
+        let defaults = v.skipped.iter().map(|(_, default)| default);
+        quote! {
+            #partial_name::#ident {
+                #(#fields: ::std::option::Option::Some(#bindings),)*
+            } => ::std::result::Result::Ok(#name::#ident {
+                #(#fields: #bindings,)*
+                #(#skipped: #defaults,)*
+            }),
+        }
+    });
+    let from = partial_variants.iter().map(|v| {
+        let ident = v.ident;
+        let fields: Vec<&Ident> = v.optional.iter().map(|(field, _)| *field).collect();
+        let bindings: Vec<Ident> = fields.iter().map(|f| binding("value", f)).collect();
+        quote! {
+            #name::#ident { #(#fields: #bindings,)* .. } => #partial_name::#ident {
+                #(#fields: ::std::option::Option::Some(#bindings),)*
+            },
+        }
+    });
+
+    let struct_doc = format!(
+        "A `{}` where every field of every variant is optional.",
+        name
+    );
+    let build_doc = format!(
+        "Builds a `{}` out of this partial.\n\nFails if not all fields of the variant are set, see `is_complete`.",
+        name
+    );
+    let object_id = name.to_string();
+
+    let type_attrs = struct_attrs.type_attrs();
+
+    Ok(quote! {
+        #[doc = #struct_doc]
+        #[derive(PartialEq, Clone, Debug)]
+        #type_attrs
+        #[allow(clippy::option_option)]
+        #vis enum #partial_name #generics #where_clause {
+            #(#declarations,)*
+        }
+
+        impl #impl_generics #partial_ty #where_clause {
+            /// add all attributes set here in to the given Partial
+            ///
+            /// If an attribute in here is already set in `obj`, then it gets
+            /// overwritten. If `obj` is another variant, it is replaced by a
+            /// copy of this partial.
+            pub fn merge_into(&self, obj: &mut Self) {
+                #[allow(unreachable_patterns)]
+                match (self, obj) {
+                    #(
+                        (#partial_name::#self_patterns, #partial_name::#obj_patterns) => {
+                            #(
+                                if let ::std::option::Option::Some(value) = #self_bindings {
+                                    *#obj_bindings = ::std::option::Option::Some(value.clone());
+                                }
+                            )*
+                        }
+                    )*
+                    (partial, obj) => *obj = partial.clone(),
+                }
+            }
+
+            /// check all attributes that are present on BOTH objects, if they
+            /// are equal. If there are no attributes present in both, will
+            /// return true.
+            ///
+            /// Partials of different variants are never equal.
+            pub fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+                #[allow(unreachable_patterns)]
+                match (self, obj) {
+                    #(
+                        (#partial_name::#self_patterns, #partial_name::#obj_patterns) => {
+                            #(
+                                if let (
+                                    ::std::option::Option::Some(lhs),
+                                    ::std::option::Option::Some(rhs),
+                                ) = (#self_bindings, #obj_bindings)
+                                {
+                                    if !::derive_patch::patchable::PatchableField::compare(lhs, rhs) {
+                                        return false;
+                                    }
+                                }
+                            )*
+                            true
+                        }
+                    )*
+                    _ => false,
+                }
+            }
+
+            #[doc = #build_doc]
+            #[allow(clippy::result_unit_err)]
+            pub fn build(&self) -> ::std::result::Result<#name_ty, ()> {
+                ::std::result::Result::Ok(match self {
+                    #(#partial_name::#self_patterns => #build,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Base<()> for #partial_ty #where_clause {
+            type Target = #name_ty;
+
+            /// The number of fields of the largest variant.
+            const MAX_FIELDS: usize = #max_fields;
+
+            /// returns true if all fields of the variant are set.
+            fn is_complete(&self) -> bool {
+                match self {
+                    #(#partial_name::#self_patterns => #is_complete,)*
+                }
+            }
+
+            fn is_empty(&self) -> bool {
+                match self {
+                    #(#partial_name::#self_patterns => #is_empty,)*
+                }
+            }
+
+            fn count(&self) -> u32 {
+                match self {
+                    #(#partial_name::#self_patterns => #count,)*
+                }
+            }
+
+            /// Sets the fields of `obj` if it is the same variant as this
+            /// partial. Otherwise `obj` is replaced if this partial is
+            /// complete, and left unchanged if not.
+            fn apply(&self, obj: &mut #name_ty) {
+                #[allow(unreachable_patterns)]
+                match (self, obj) {
+                    #(
+                        (#partial_name::#self_patterns, #name::#obj_patterns) => {
+                            #(
+                                if let ::std::option::Option::Some(value) = #self_bindings {
+                                    *#obj_bindings = value.clone();
+                                }
+                            )*
+                        }
+                    )*
+                    (partial, obj) => {
+                        if let ::std::result::Result::Ok(value) = partial.build() {
+                            *obj = value;
+                        }
+                    }
+                }
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Partial for #partial_ty #where_clause {
+            fn build(&self) -> ::std::result::Result<#name_ty, ()> {
+                #partial_name::build(self)
+            }
+
+            fn merge_into(&self, obj: &mut Self) {
+                #partial_name::merge_into(self, obj)
+            }
+
+            fn is_partial_equal_existing(&self, obj: &Self) -> bool {
+                #partial_name::is_partial_equal_existing(self, obj)
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::PartialExt for #name_ty #where_clause {
+            type Partial = #partial_ty;
+        }
+
+        impl #impl_generics ::std::convert::From<#name_ty> for #partial_ty #where_clause {
+            fn from(value: #name_ty) -> Self {
+                match value {
+                    #(#from)*
+                }
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<#partial_ty> for #name_ty #where_clause {
+            type Error = ::derive_patch::mismatch::IncompleteError<#partial_ty>;
+
+            fn try_from(value: #partial_ty) -> ::std::result::Result<Self, Self::Error> {
+                #[allow(unreachable_patterns)]
+                match value {
+                    #(#try_from)*
+                    value => ::std::result::Result::Err(::derive_patch::mismatch::IncompleteError::new(
+                        "try_from",
+                        #object_id,
+                        value,
+                    )),
+                }
+            }
+        }
+    })
+}
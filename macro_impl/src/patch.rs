@@ -0,0 +1,980 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Code generation for `#[derive(Patch)]`.
+
+use crate::{
+    attrs::{cfg_attrs, reject_cfg, PartialFieldAttrs, PatchFieldAttrs, StructAttrs},
+    bounds::Bounds,
+    extra::ExtraFields,
+    input::{
+        cfg_chain_or_true, cfg_sum_or_zero, member, member_name, struct_body, struct_fields,
+        Declaration, StructFields,
+    },
+    partial, patch_enum,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    spanned::Spanned, Attribute, Data, DeriveInput, Error, Ident, Index, Member, Result, Type,
+};
+
+/// A field of the struct the patch is generated for.
+struct PatchField<'a> {
+    /// The field in the original struct.
+    member: Member,
+    /// The field in the patch, differs from `member` for tuple structs with
+    /// skipped fields.
+    slot: Member,
+    /// The field in the partial.
+    partial_slot: Member,
+    /// The name of the field in docs and mismatch errors.
+    name: String,
+    /// The argument for an id field in `new` and `new_with_partial`.
+    param: Ident,
+    ty: &'a Type,
+    attrs: PatchFieldAttrs,
+    /// How the field is stored in the `Partial`
+    partial: PartialFieldAttrs,
+    /// The attributes of the field in the original struct.
+    field_attrs: &'a [Attribute],
+    /// The `#[cfg(...)]` attributes of the field, put on all code for it.
+    cfg: TokenStream,
+}
+
+impl<'a> PatchField<'a> {
+    /// `statement`, only if the field exists.
+    fn with_cfg(&self, statement: TokenStream) -> TokenStream {
+        if self.cfg.is_empty() {
+            statement
+        } else {
+            let cfg = &self.cfg;
+            quote!(#cfg { #statement })
+        }
+    }
+
+    /// Adds the bounds the patch needs for this field.
+    fn add_bounds(&self, bounds: &mut Bounds) {
+        let ty = self.ty;
+        if self.attrs.id.is_some() {
+            bounds.add(
+                ty,
+                quote! {
+                    ::std::clone::Clone
+                        + ::std::cmp::PartialEq
+                        + ::std::fmt::Debug
+                        + ::derive_patch::patchable::PatchableField
+                },
+            );
+            return;
+        }
+        bounds.add(
+            &self.diff(),
+            quote!(::derive_patch::diff::Diff<Object = #ty> + ::std::clone::Clone),
+        );
+        if self.cfg.is_empty() {
+            add_diff_assertion_bound(bounds, &self.attrs, ty);
+        }
+        if self.attrs.is_copy_diff() {
+            bounds.add(ty, quote!(::derive_patch::patchable::PatchableField));
+        }
+        if self.attrs.nested.is_some() {
+            bounds.add(ty, quote!(::derive_patch::traits::PatchableExt));
+        }
+        if self.partial.nested.is_some() {
+            bounds.add(ty, quote!(::std::clone::Clone));
+        }
+    }
+
+    /// The `Diff` type stored in the patch for this field.
+    fn diff(&self) -> TokenStream {
+        self.attrs.diff_type(self.ty)
+    }
+}
+
+/// Requires the custom diff of a field of type `ty` to work on it, so a diff
+/// of another type is reported by `diff_assertion` only, instead of by every
+/// call of the diff as well.
+pub(crate) fn add_diff_assertion_bound(bounds: &mut Bounds, attrs: &PatchFieldAttrs, ty: &Type) {
+    if attrs.diff.is_some() {
+        bounds.add_deferred(
+            &attrs.diff_type(ty),
+            quote!(::derive_patch::diff::Diff<Object = #ty>),
+        );
+    }
+}
+
+/// Makes sure the custom diff of a field of type `ty` works on it, the error
+/// points at the diff.
+pub(crate) fn diff_assertion(attrs: &PatchFieldAttrs, ty: &Type) -> Option<TokenStream> {
+    let span = attrs.diff.as_ref()?.span();
+    let diff = attrs.diff_type(ty);
+    Some(quote_spanned! {span=>
+        diff_object_must_match_field_type::<#diff, #ty>();
+    })
+}
+
+/// Puts the `diff_assertion`s of all fields into a constant.
+pub(crate) fn diff_assertions(assertions: Vec<TokenStream>) -> TokenStream {
+    if assertions.is_empty() {
+        return quote!();
+    }
+    quote! {
+        const _: fn() = || {
+            fn diff_object_must_match_field_type<D, T>()
+            where
+                D: ::derive_patch::diff::Diff<Object = T>,
+            {
+            }
+            #(#assertions)*
+        };
+    }
+}
+
+/// Generates the `<Name>Patch` struct and all its impls for `input`.
+///
+/// The patch of a tuple struct is a tuple struct as well. The patch of a
+/// newtype additionally implements `Diff`, forwarding to the diff of the
+/// wrapped field.
+pub(crate) fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    if let Data::Enum(data) = &input.data {
+        return patch_enum::derive(input, data);
+    }
+
+    let StructFields {
+        fields: struct_fields,
+        is_tuple,
+    } = struct_fields(input, "Patch")?;
+    let mut fields = Vec::new();
+    let mut partial_slot = 0;
+    for (index, f) in struct_fields.iter().enumerate() {
+        if is_tuple {
+            reject_cfg(&f.attrs, "fields of tuple structs")?;
+        }
+        let attrs = PatchFieldAttrs::parse(&f.attrs)?;
+        let partial = PartialFieldAttrs::parse(&f.attrs)?;
+        let member = member(index, f);
+        let slot = if is_tuple {
+            Member::Unnamed(Index::from(fields.len()))
+        } else {
+            member.clone()
+        };
+        let in_partial = if is_tuple {
+            Member::Unnamed(Index::from(partial_slot))
+        } else {
+            member.clone()
+        };
+        if partial.skip.is_none() {
+            partial_slot += 1;
+        }
+        if attrs.skip.is_none() {
+            fields.push(PatchField {
+                slot,
+                partial_slot: in_partial,
+                name: member_name(&member),
+                param: match &f.ident {
+                    Some(ident) => ident.clone(),
+                    None => format_ident!("field_{}", index),
+                },
+                member,
+                ty: &f.ty,
+                attrs,
+                partial,
+                field_attrs: &f.attrs,
+                cfg: cfg_attrs(&f.attrs),
+            });
+        }
+    }
+
+    for field in &fields {
+        if let (Some(id), Some(diff)) = (&field.attrs.id, &field.attrs.diff) {
+            let mut error = Error::new_spanned(diff, "id fields are not diffed");
+            error.combine(Error::new_spanned(id, "field is marked as id here"));
+            return Err(error);
+        }
+    }
+
+    let name = &input.ident;
+    let struct_attrs = StructAttrs::parse(&input.attrs, "patch")?;
+    let vis = struct_attrs.vis(&input.vis);
+    let patch_name = struct_attrs.name(format_ident!("{}Patch", name));
+    // the patch is created out of the partial, which may be renamed
+    let partial_name =
+        StructAttrs::parse(&input.attrs, "partial")?.name(format_ident!("Partial{}", name));
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let name_ty = quote!(#name #ty_generics);
+    let patch_ty = quote!(#patch_name #ty_generics);
+    let partial_ty = quote!(#partial_name #ty_generics);
+
+    let (ids, diffed): (Vec<&PatchField>, Vec<&PatchField>) =
+        fields.iter().partition(|f| f.attrs.id.is_some());
+
+    let extra = ExtraFields::new(
+        &struct_attrs.extra,
+        is_tuple,
+        fields.iter().map(|f| &f.member),
+    )?;
+    let extra_fields = extra.declarations();
+    let extra_init = extra.initializers();
+    let extra_clones = extra.clones();
+    let extra_accessors = extra.accessors();
+
+    // `new_with_partial` uses the partial, which has bounds of its own
+    let mut bounds = Bounds::new(generics, struct_attrs.bound.as_ref());
+    partial::add_bounds(&mut bounds, struct_fields)?;
+    for f in &fields {
+        f.add_bounds(&mut bounds);
+    }
+    let where_clause = bounds.where_clause();
+
+    let slots: Vec<&Member> = diffed.iter().map(|f| &f.slot).collect();
+    let members: Vec<&Member> = diffed.iter().map(|f| &f.member).collect();
+    let diffs: Vec<TokenStream> = diffed.iter().map(|f| f.diff()).collect();
+    let cfgs: Vec<&TokenStream> = diffed.iter().map(|f| &f.cfg).collect();
+    let field_names: Vec<&String> = diffed.iter().map(|f| &f.name).collect();
+    let max_fields = if cfgs.iter().all(|cfg| cfg.is_empty()) {
+        let max_fields = slots.len();
+        quote!(#max_fields)
+    } else {
+        cfg_sum_or_zero(diffed.iter().map(|f| (f.cfg.clone(), quote!(1))))
+    };
+
+    let id_slots: Vec<&Member> = ids.iter().map(|f| &f.slot).collect();
+    let id_members: Vec<&Member> = ids.iter().map(|f| &f.member).collect();
+    let id_params: Vec<&Ident> = ids.iter().map(|f| &f.param).collect();
+    let id_types: Vec<&Type> = ids.iter().map(|f| f.ty).collect();
+    let id_cfgs: Vec<&TokenStream> = ids.iter().map(|f| &f.cfg).collect();
+
+    let from_partial = diffed.iter().map(|f| {
+        let member = &f.member;
+        let partial_slot = &f.partial_slot;
+        let diff = f.diff();
+        let diff_to = |value: TokenStream| {
+            quote! {{
+                let diff = <#diff as ::derive_patch::diff::Diff>::new(&obj.#member, #value);
+                if ::derive_patch::diff::Diff::contains_change(&diff) {
+                    ::std::option::Option::Some(diff)
+                } else {
+                    ::std::option::Option::None
+                }
+            }}
+        };
+        if f.partial.skip.is_some() {
+            quote!(::std::option::Option::None)
+        } else if f.partial.force.is_some() {
+            // forced fields are always present in the partial
+            diff_to(quote!(&partial.#partial_slot))
+        } else if f.partial.nested.is_some() {
+            // nested partials only contain some fields, apply them onto a copy
+            let new_value = diff_to(quote!(&new_value));
+            quote! {
+                match &partial.#partial_slot {
+                    ::std::option::Option::Some(value) => {
+                        let mut new_value = ::std::clone::Clone::clone(&obj.#member);
+                        ::derive_patch::traits::Base::apply(value, &mut new_value);
+                        #new_value
+                    }
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            }
+        } else {
+            let from_value = diff_to(quote!(value));
+            quote! {
+                match &partial.#partial_slot {
+                    ::std::option::Option::Some(value) => #from_value,
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            }
+        }
+    });
+
+    // generic fields are checked by the bounds instead
+    let diff_assertions = diff_assertions(
+        diffed
+            .iter()
+            .filter(|f| !bounds.is_generic(f.ty))
+            .filter_map(|f| Some(f.with_cfg(diff_assertion(&f.attrs, f.ty)?)))
+            .collect(),
+    );
+
+    let is_complete = cfg_chain_or_true(diffed.iter().map(|f| {
+        let slot = &f.slot;
+        (f.cfg.clone(), quote!(self.#slot.is_some()))
+    }));
+    let is_empty = cfg_chain_or_true(diffed.iter().map(|f| {
+        let slot = &f.slot;
+        (f.cfg.clone(), quote!(self.#slot.is_none()))
+    }));
+    let count = if slots.is_empty() {
+        quote!(0)
+    } else {
+        let count = diffed.iter().map(|f| {
+            let slot = &f.slot;
+            f.with_cfg(quote! {
+                if self.#slot.is_some() {
+                    count += 1;
+                }
+            })
+        });
+        quote! {
+            let mut count = 0;
+            #(#count)*
+            count
+        }
+    };
+    let apply = diffed.iter().map(|f| {
+        let (slot, member) = (&f.slot, &f.member);
+        f.with_cfg(quote! {
+            if let ::std::option::Option::Some(diff) = &self.#slot {
+                // `apply` checked `can_apply_cleanly` before
+                let applied = ::derive_patch::diff::Diff::apply_into(diff, &mut obj.#member);
+                ::std::debug_assert!(applied.is_ok(), "the diff was checked before");
+            }
+        })
+    });
+    let cleanup = diffed.iter().map(|f| {
+        let slot = &f.slot;
+        f.with_cfg(quote! {
+            if let ::std::option::Option::Some(diff) = &mut self.#slot {
+                if ::derive_patch::diff::Diff::cleanup(diff) {
+                    changed = true;
+                }
+                if !::derive_patch::diff::Diff::contains_change(diff) {
+                    self.#slot = ::std::option::Option::None;
+                    changed = true;
+                }
+            }
+        })
+    });
+    let check = diffed.iter().map(|f| {
+        let (slot, member, name) = (&f.slot, &f.member, &f.name);
+        let diff = f.diff();
+        f.with_cfg(quote! {
+            if let ::std::option::Option::Some(diff) = &self.#slot {
+                if let ::std::result::Result::Err(mut e) =
+                    <#diff as ::derive_patch::diff::Diff>::check(diff, &obj.#member)
+                {
+                    e.prefix_fields(#name);
+                    error.merge(&e);
+                }
+            }
+        })
+    });
+
+    let same_fields = cfg_chain_or_true(diffed.iter().map(|f| {
+        let slot = &f.slot;
+        (
+            f.cfg.clone(),
+            quote!(self.#slot.is_some() == next.#slot.is_some()),
+        )
+    }));
+    let merged: Vec<Ident> = diffed
+        .iter()
+        .enumerate()
+        .map(|(i, _)| format_ident!("merged_{}", i))
+        .collect();
+    let coalesce_merge = diffed.iter().zip(&merged).map(|(f, merged)| {
+        let (slot, cfg) = (&f.slot, &f.cfg);
+        quote! {
+            #cfg
+            let #merged = match (&self.#slot, &next.#slot) {
+                (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                    let mut lhs = ::std::clone::Clone::clone(lhs);
+                    if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                        return false;
+                    }
+                    ::std::option::Option::Some(lhs)
+                }
+                _ => ::std::option::Option::None,
+            };
+        }
+    });
+    let assign_merged = |target: TokenStream| -> Vec<TokenStream> {
+        diffed
+            .iter()
+            .zip(&merged)
+            .map(|(f, merged)| {
+                let slot = &f.slot;
+                f.with_cfg(quote!(#target.#slot = #merged;))
+            })
+            .collect()
+    };
+    let coalesce_assign = assign_merged(quote!(self));
+    let merge_fields = diffed.iter().zip(&merged).map(|(f, merged)| {
+        let (slot, cfg, name) = (&f.slot, &f.cfg, &f.name);
+        quote! {
+            #cfg
+            let #merged = match (&target.#slot, &self.#slot) {
+                (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                    let mut lhs = ::std::clone::Clone::clone(lhs);
+                    if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                        error.add_error(::derive_patch::mismatch::MismatchError::new(
+                            #name,
+                            "a change of the merged value",
+                            "a conflicting change",
+                            ::derive_patch::mismatch::MismatchType::MergeConflict,
+                        ));
+                    }
+                    ::std::option::Option::Some(lhs)
+                }
+                (lhs, rhs) => ::std::option::Option::or(lhs.as_ref(), rhs.as_ref()).cloned(),
+            };
+        }
+    });
+    let merge_assign = assign_merged(quote!(target));
+    let overwrite_fields = diffed.iter().map(|f| {
+        let slot = &f.slot;
+        f.with_cfg(quote! {
+            if let ::std::option::Option::Some(diff) = &self.#slot {
+                target.#slot = ::std::option::Option::Some(::std::clone::Clone::clone(diff));
+            }
+        })
+    });
+    let rebase_fields = diffed.iter().zip(&merged).map(|(f, merged)| {
+        let (slot, member, cfg, name) = (&f.slot, &f.member, &f.cfg, &f.name);
+        let diff = f.diff();
+        quote! {
+            #cfg
+            let #merged = match &self.#slot {
+                ::std::option::Option::Some(diff) => {
+                    let mut diff = ::std::clone::Clone::clone(diff);
+                    if let ::std::result::Result::Err(mut e) =
+                        <#diff as ::derive_patch::diff::Diff>::rebase(&mut diff, &obj.#member)
+                    {
+                        e.prefix_fields(#name);
+                        error.merge(&e);
+                    }
+                    ::std::option::Option::Some(diff)
+                }
+                ::std::option::Option::None => ::std::option::Option::None,
+            };
+        }
+    });
+    let concurrent_fields = diffed.iter().zip(&merged).map(|(f, merged)| {
+        let (slot, cfg, name) = (&f.slot, &f.cfg, &f.name);
+        let resolve_fn = f.attrs.resolve_fn();
+        quote! {
+            #cfg
+            let #merged = match (&self.#slot, &other.#slot) {
+                (::std::option::Option::Some(ours), ::std::option::Option::Some(theirs)) => {
+                    #resolve_fn(
+                        #name,
+                        ours,
+                        theirs,
+                        resolver,
+                        &mut conflicts,
+                    )
+                }
+                (ours, theirs) => ::std::option::Option::or(ours.as_ref(), theirs.as_ref()).cloned(),
+            };
+        }
+    });
+    let other_target_conflicts = ids.iter().map(|f| {
+        let (slot, name) = (&f.slot, &f.name);
+        f.with_cfg(quote! {
+            if !::derive_patch::patchable::PatchableField::compare(&self.#slot, &other.#slot) {
+                conflicts.push(::derive_patch::conflict::Conflict::new(
+                    #name,
+                    ::derive_patch::mismatch::MismatchType::ObjectID,
+                    false,
+                ));
+            }
+        })
+    });
+    // merging and overwriting needs patches of the same target
+    let same_target_checks: Vec<TokenStream> = ids
+        .iter()
+        .map(|f| {
+            let (slot, name) = (&f.slot, &f.name);
+            f.with_cfg(quote! {
+                if !::derive_patch::patchable::PatchableField::compare(&self.#slot, &target.#slot) {
+                    error.add_error(::derive_patch::mismatch::MismatchError::new(
+                        #name,
+                        ::std::format!("{:?}", target.#slot),
+                        ::std::format!("{:?}", self.#slot),
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                    ));
+                }
+            })
+        })
+        .collect();
+
+    let is_correct_target = if ids.is_empty() {
+        quote! {
+            fn is_correct_target(
+                &self,
+                _obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                ::std::result::Result::Ok(())
+            }
+        }
+    } else {
+        let id_checks = ids.iter().map(|f| {
+            let (slot, member, name) = (&f.slot, &f.member, &f.name);
+            f.with_cfg(quote! {
+                if !::derive_patch::patchable::PatchableField::compare(&self.#slot, &obj.#member) {
+                    error.add_error(::derive_patch::mismatch::MismatchError::new(
+                        #name,
+                        ::std::format!("{:?}", obj.#member),
+                        ::std::format!("{:?}", self.#slot),
+                        ::derive_patch::mismatch::MismatchType::ObjectID,
+                    ));
+                }
+            })
+        });
+        quote! {
+            fn is_correct_target(
+                &self,
+                obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+
+                #(#id_checks)*
+
+                if error.is_error_free() {
+                    ::std::result::Result::Ok(())
+                } else {
+                    ::std::result::Result::Err(error)
+                }
+            }
+        }
+    };
+    let is_same_target = if ids.is_empty() {
+        quote! {
+            fn is_same_target(&self, _other: &Self) -> bool {
+                true
+            }
+        }
+    } else {
+        let same_ids = cfg_chain_or_true(ids.iter().map(|f| {
+            let slot = &f.slot;
+            (
+                f.cfg.clone(),
+                quote!(::derive_patch::patchable::PatchableField::compare(&self.#slot, &other.#slot)),
+            )
+        }));
+        quote! {
+            fn is_same_target(&self, other: &Self) -> bool {
+                #same_ids
+            }
+        }
+    };
+
+    // a patch can be inverted if all its diffs can. The bounds are higher-ranked,
+    // so they are only checked where the patch is inverted and the impl does
+    // not apply for other diffs, instead of failing to compile.
+    let invert_bounds: Vec<TokenStream> = diffed
+        .iter()
+        .map(|f| {
+            let diff = f.diff();
+            quote!(for<'__invert> #diff: ::derive_patch::diff::Invert)
+        })
+        .collect();
+    let invert_where_clause = bounds.where_clause_with(invert_bounds.iter().cloned());
+
+    // a newtype patch is a diff of the newtype, using the diff of its field
+    let newtype_diff = match (struct_fields.len(), diffed.as_slice()) {
+        (1, [field]) if is_tuple => {
+            let diff = field.diff();
+            let where_clause = bounds.where_clause_with(Some(quote!(#diff: ::std::clone::Clone)));
+            let invert_where_clause = bounds.where_clause_with(
+                invert_bounds
+                    .iter()
+                    .cloned()
+                    .chain(Some(quote!(#diff: ::std::clone::Clone))),
+            );
+            quote! {
+                impl #impl_generics ::std::convert::From<#diff> for #patch_ty #where_clause {
+                    fn from(diff: #diff) -> Self {
+                        #patch_name(::std::option::Option::Some(diff))
+                    }
+                }
+
+                impl #impl_generics ::derive_patch::diff::Diff for #patch_ty #where_clause {
+                    type DiffResult = <#diff as ::derive_patch::diff::Diff>::DiffResult;
+                    type Object = #name_ty;
+
+                    fn new(old: &#name_ty, new: &#name_ty) -> Self {
+                        ::derive_patch::traits::PatchableExt::get_patch(old, new)
+                    }
+
+                    fn changes_object(&self, old: &#name_ty) -> bool {
+                        self.0.as_ref().map_or(false, |diff| {
+                            ::derive_patch::diff::Diff::changes_object(diff, &old.0)
+                        })
+                    }
+
+                    fn contains_change(&self) -> bool {
+                        self.0
+                            .as_ref()
+                            .map_or(false, ::derive_patch::diff::Diff::contains_change)
+                    }
+
+                    fn merge(&mut self, rhs: &Self) -> ::std::result::Result<(), ()> {
+                        match (&mut self.0, &rhs.0) {
+                            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                                ::derive_patch::diff::Diff::merge(lhs, rhs)
+                            }
+                            (lhs @ ::std::option::Option::None, rhs) => {
+                                *lhs = rhs.clone();
+                                ::std::result::Result::Ok(())
+                            }
+                            (_, ::std::option::Option::None) => ::std::result::Result::Ok(()),
+                        }
+                    }
+
+                    fn applies_cleanly(
+                        &self,
+                        obj: &#name_ty,
+                    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MismatchError> {
+                        match &self.0 {
+                            ::std::option::Option::Some(diff) => {
+                                ::derive_patch::diff::Diff::applies_cleanly(diff, &obj.0)
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Ok(()),
+                        }
+                    }
+
+                    fn check(
+                        &self,
+                        obj: &#name_ty,
+                    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                        match &self.0 {
+                            ::std::option::Option::Some(diff) => {
+                                ::derive_patch::diff::Diff::check(diff, &obj.0)
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Ok(()),
+                        }
+                    }
+
+                    fn combine(&self, concurrent: &Self) -> ::std::option::Option<Self> {
+                        match (&self.0, &concurrent.0) {
+                            (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                                ::derive_patch::diff::Diff::combine(lhs, rhs)
+                                    .map(|diff| #patch_name(::std::option::Option::Some(diff)))
+                            }
+                            (lhs, rhs) => ::std::option::Option::Some(#patch_name(
+                                ::std::option::Option::or(lhs.as_ref(), rhs.as_ref()).cloned(),
+                            )),
+                        }
+                    }
+
+                    fn rebase(
+                        &mut self,
+                        obj: &#name_ty,
+                    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                        match &mut self.0 {
+                            ::std::option::Option::Some(diff) => {
+                                ::derive_patch::diff::Diff::rebase(diff, &obj.0)
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Ok(()),
+                        }
+                    }
+
+                    fn cleanup(&mut self) -> bool {
+                        ::derive_patch::traits::Patch::cleanup(self)
+                    }
+
+                    fn apply_into(
+                        &self,
+                        obj: &mut #name_ty,
+                    ) -> ::std::result::Result<(), ::derive_patch::mismatch::MismatchError> {
+                        match &self.0 {
+                            ::std::option::Option::Some(diff) => {
+                                ::derive_patch::diff::Diff::apply_into(diff, &mut obj.0)
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Ok(()),
+                        }
+                    }
+                }
+
+                impl #impl_generics ::derive_patch::diff::Invert for #patch_ty #invert_where_clause {
+                    fn invert(&self) -> Self {
+                        ::derive_patch::traits::InvertPatch::invert(self)
+                    }
+                }
+            }
+        }
+        _ => quote!(),
+    };
+
+    let struct_doc = format!("A patch for `{}`.", name);
+    let field_list: String = field_names
+        .iter()
+        .map(|field| format!("\n- `{}`", field))
+        .collect();
+    let max_fields_doc = format!("Available fields{}", field_list);
+    // named patches declare the diffs first, tuple patches keep the order of
+    // the struct
+    let declared: Vec<&PatchField> = if is_tuple {
+        fields.iter().collect()
+    } else {
+        diffed.iter().chain(&ids).copied().collect()
+    };
+    let declarations: Vec<Declaration> = declared
+        .iter()
+        .map(|f| {
+            let ty = f.ty;
+            let forwarded = struct_attrs.forwarded(f.field_attrs);
+            let cfg = &f.cfg;
+            if f.attrs.id.is_some() {
+                let doc = format!("Has to match `{}` of the patched object.", f.name);
+                Declaration {
+                    member: f.slot.clone(),
+                    attrs: quote!(#[doc = #doc] #forwarded #cfg),
+                    ty: quote!(#ty),
+                }
+            } else {
+                let diff = f.diff();
+                Declaration {
+                    member: f.slot.clone(),
+                    attrs: quote!(#forwarded #cfg),
+                    ty: quote!(::std::option::Option<#diff>),
+                }
+            }
+        })
+        .collect();
+    let body = struct_body(is_tuple, &declarations, &where_clause, extra_fields);
+
+    let type_attrs = struct_attrs.type_attrs();
+
+    Ok(quote! {
+        impl #impl_generics ::derive_patch::patchable::PatchableField for #name_ty #where_clause {}
+
+        impl #impl_generics ::derive_patch::traits::PatchableExt for #name_ty #where_clause {
+            type Patch = #patch_ty;
+
+            fn get_patch(&self, rhs: &Self) -> #patch_ty {
+                #patch_name {
+                    #(#cfgs #slots: {
+                        let diff = <#diffs as ::derive_patch::diff::Diff>::new(&self.#members, &rhs.#members);
+                        if ::derive_patch::diff::Diff::contains_change(&diff) {
+                            ::std::option::Option::Some(diff)
+                        } else {
+                            ::std::option::Option::None
+                        }
+                    },)*
+                    #(#id_cfgs #id_slots: ::std::clone::Clone::clone(&self.#id_members),)*
+                    #extra_init
+                }
+            }
+        }
+
+        #[doc = #struct_doc]
+        #[derive(PartialEq, Debug, Clone)]
+        #type_attrs
+        #vis struct #patch_name #generics #body
+
+        impl #impl_generics #patch_ty #where_clause {
+            /// Generates an empty patch for the object with the given ids.
+            #[allow(clippy::new_without_default)]
+            pub fn new(#(#id_cfgs #id_params: #id_types),*) -> Self {
+                #patch_name {
+                    #(#cfgs #slots: ::std::option::Option::None,)*
+                    #(#id_cfgs #id_slots: #id_params,)*
+                    #extra_init
+                }
+            }
+
+            /// Generates a patch that changes `obj` to the values set in
+            /// `partial`.
+            ///
+            /// Fields of `partial` that equal the current value of `obj` are
+            /// left out.
+            pub fn new_with_partial(
+                obj: &#name_ty,
+                partial: &#partial_ty,
+                #(#id_cfgs #id_params: #id_types),*
+            ) -> Self {
+                #patch_name {
+                    #(#cfgs #slots: #from_partial,)*
+                    #(#id_cfgs #id_slots: #id_params,)*
+                    #extra_init
+                }
+            }
+
+            #extra_accessors
+
+            /// internal apply function. Responsible for actually assigning the
+            /// values.
+            ///
+            /// This function performs no checks, it's the responsibility of the
+            /// caller to perform checks.
+            ///
+            /// Used by `apply`, after `can_apply_cleanly` succeeded.
+            fn _apply(&self, obj: &mut #name_ty) {
+                #(#apply)*
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Base<::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError>>
+            for #patch_ty
+            #where_clause
+        {
+            type Target = #name_ty;
+
+            #[doc = #max_fields_doc]
+            const MAX_FIELDS: usize = #max_fields;
+
+            /// returns true if ALL fields have an associated diff.
+            fn is_complete(&self) -> bool {
+                #is_complete
+            }
+
+            fn is_empty(&self) -> bool {
+                #is_empty
+            }
+
+            fn count(&self) -> u32 {
+                #count
+            }
+
+            /// applies the patch to the given object
+            ///
+            /// makes sure the id fields of the object match the ones of this
+            /// patch. Returns an Error if that is not the case.
+            ///
+            /// makes sure that all old_values of the patches field are the
+            /// current value of the given object. Returns an Error otherwise.
+            ///
+            /// This function does not change `obj` if an Error occurs.
+            fn apply(
+                &self,
+                obj: &mut #name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                ::derive_patch::traits::Patch::is_correct_target(self, obj)?;
+                ::derive_patch::traits::Patch::can_apply_cleanly(self, obj)?;
+
+                self._apply(obj);
+
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Patch for #patch_ty #where_clause {
+            fn cleanup(&mut self) -> bool {
+                let mut changed = false;
+                #(#cleanup)*
+                changed
+            }
+
+            #is_correct_target
+
+            fn can_apply_cleanly(
+                &self,
+                obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+
+                #(#check)*
+
+                if error.is_error_free() {
+                    ::std::result::Result::Ok(())
+                } else {
+                    ::std::result::Result::Err(error)
+                }
+            }
+
+            #is_same_target
+
+            fn coalesce(&mut self, next: &Self) -> bool {
+                if !(#same_fields) || !::derive_patch::traits::Patch::is_same_target(self, next) {
+                    return false;
+                }
+                #(#coalesce_merge)*
+                #(#coalesce_assign)*
+                true
+            }
+
+            fn merge_into(
+                &self,
+                target: &mut Self,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+                #(#same_target_checks)*
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+
+                #(#merge_fields)*
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+                #(#merge_assign)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn overwrite_into(
+                &self,
+                target: &mut Self,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+                #(#same_target_checks)*
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+
+                #(#overwrite_fields)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn rebase(
+                &mut self,
+                obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                ::derive_patch::traits::Patch::is_correct_target(self, obj)?;
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+
+                #(#rebase_fields)*
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+                #(#coalesce_assign)*
+                ::std::result::Result::Ok(())
+            }
+
+            fn merge_concurrent<R>(
+                &self,
+                other: &Self,
+                resolver: &mut R,
+            ) -> ::std::result::Result<Self, ::std::vec::Vec<::derive_patch::conflict::Conflict>>
+            where
+                R: ::derive_patch::conflict::ConflictResolver + ?::std::marker::Sized,
+            {
+                let mut conflicts = ::std::vec::Vec::new();
+                #(#other_target_conflicts)*
+                if !conflicts.is_empty() {
+                    return ::std::result::Result::Err(conflicts);
+                }
+
+                #(#concurrent_fields)*
+                if !conflicts.is_empty() {
+                    return ::std::result::Result::Err(conflicts);
+                }
+                ::std::result::Result::Ok(#patch_name {
+                    #(#cfgs #slots: #merged,)*
+                    #(#id_cfgs #id_slots: ::std::clone::Clone::clone(&self.#id_slots),)*
+                    #extra_clones
+                })
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::InvertPatch for #patch_ty #invert_where_clause {
+            fn invert(&self) -> Self {
+                #patch_name {
+                    #(#cfgs #slots: self.#slots.as_ref().map(::derive_patch::diff::Invert::invert),)*
+                    #(#id_cfgs #id_slots: ::std::clone::Clone::clone(&self.#id_slots),)*
+                    #extra_clones
+                }
+            }
+        }
+
+        #newtype_diff
+
+        #diff_assertions
+    })
+}
@@ -0,0 +1,848 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Code generation for `#[derive(Patch)]` on enums.
+
+use crate::{
+    attrs::{PartialFieldAttrs, PatchFieldAttrs, StructAttrs},
+    bounds::Bounds,
+    input::{binding, chain_or_true, sum_or_zero, variants},
+    partial,
+    patch::{add_diff_assertion_bound, diff_assertion, diff_assertions},
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{DataEnum, DeriveInput, Error, Ident, Result, Type};
+
+/// The variant of the patch that replaces the whole object.
+const REPLACE: &str = "Replace";
+
+/// A field of a variant of the enum the patch is generated for.
+struct PatchField<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    attrs: PatchFieldAttrs,
+    /// False if the field is skipped by the `Partial`
+    in_partial: bool,
+    /// The attributes copied to the field of the patch.
+    forwarded: TokenStream,
+}
+
+/// A variant of the enum the patch is generated for.
+struct PatchVariant<'a> {
+    ident: &'a Ident,
+    is_unit: bool,
+    /// The fields that are part of the patch.
+    fields: Vec<PatchField<'a>>,
+}
+
+impl<'a> PatchVariant<'a> {
+    /// The names of the fields of this variant.
+    fn idents(&self) -> Vec<&'a Ident> {
+        self.fields.iter().map(|f| f.ident).collect()
+    }
+
+    /// The names the fields are bound to in a pattern with `prefix`.
+    fn bindings(&self, prefix: &str) -> Vec<Ident> {
+        self.fields
+            .iter()
+            .map(|f| binding(prefix, f.ident))
+            .collect()
+    }
+
+    /// `Variant { field: prefix_field, .. }`, binds all fields of the patch.
+    fn pattern(&self, prefix: &str) -> TokenStream {
+        let ident = self.ident;
+        let fields = self.idents();
+        let bindings = self.bindings(prefix);
+        quote!(#ident { #(#fields: #bindings,)* .. })
+    }
+
+    /// The `Diff` types of the fields of this variant.
+    fn diffs(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .map(|f| f.attrs.diff_type(f.ty))
+            .collect()
+    }
+}
+
+/// Creates the diff from `old` to `new` for a field of type `diff`, `None` if
+/// nothing changed.
+fn diff_or_none(diff: &TokenStream, old: TokenStream, new: TokenStream) -> TokenStream {
+    quote! {{
+        let diff = <#diff as ::derive_patch::diff::Diff>::new(#old, #new);
+        if ::derive_patch::diff::Diff::contains_change(&diff) {
+            ::std::option::Option::Some(diff)
+        } else {
+            ::std::option::Option::None
+        }
+    }}
+}
+
+/// Generates the `<Name>Patch` enum and all its impls for `input`.
+///
+/// The patch either has the same variant as the object and stores a `Diff`
+/// for every field of it, or replaces the whole object.
+pub(crate) fn derive(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream> {
+    let struct_attrs = StructAttrs::parse(&input.attrs, "patch")?;
+    if let Some(extra) = struct_attrs.extra.first() {
+        return Err(Error::new_spanned(
+            &extra.ident,
+            "extra fields are not supported on enums",
+        ));
+    }
+
+    let mut patch_variants = Vec::new();
+    for variant in variants(data, "Patch")? {
+        if variant.ident == REPLACE {
+            return Err(Error::new_spanned(
+                variant.ident,
+                "`Replace` is reserved for the variant of the patch that replaces the whole object",
+            ));
+        }
+        let mut fields = Vec::new();
+        for field in &variant.fields {
+            let attrs = PatchFieldAttrs::parse(&field.attrs)?;
+            if let Some(id) = &attrs.id {
+                return Err(Error::new_spanned(id, "`id` is not supported on enums"));
+            }
+            if attrs.skip.is_none() {
+                fields.push(PatchField {
+                    ident: field.ident.as_ref().expect("named field"),
+                    ty: &field.ty,
+                    attrs,
+                    in_partial: PartialFieldAttrs::parse(&field.attrs)?.skip.is_none(),
+                    forwarded: struct_attrs.forwarded(&field.attrs),
+                });
+            }
+        }
+        patch_variants.push(PatchVariant {
+            ident: variant.ident,
+            is_unit: variant.is_unit,
+            fields,
+        });
+    }
+
+    let name = &input.ident;
+    let vis = struct_attrs.vis(&input.vis);
+    let patch_name = struct_attrs.name(format_ident!("{}Patch", name));
+    // the patch is created out of the partial, which may be renamed
+    let partial_name =
+        StructAttrs::parse(&input.attrs, "partial")?.name(format_ident!("Partial{}", name));
+    let replace = format_ident!("{}", REPLACE);
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let name_ty = quote!(#name #ty_generics);
+    let patch_ty = quote!(#patch_name #ty_generics);
+    let partial_ty = quote!(#partial_name #ty_generics);
+
+    // `new_with_partial` uses the partial, which has bounds of its own
+    let mut bounds = Bounds::new(generics, struct_attrs.bound.as_ref());
+    partial::add_bounds(&mut bounds, data.variants.iter().flat_map(|v| &v.fields))?;
+    bounds.add(
+        &name_ty,
+        quote!(::std::clone::Clone + ::std::cmp::PartialEq + ::std::fmt::Debug),
+    );
+    for field in patch_variants.iter().flat_map(|v| &v.fields) {
+        let ty = field.ty;
+        bounds.add(
+            &field.attrs.diff_type(ty),
+            quote!(::derive_patch::diff::Diff<Object = #ty> + ::std::clone::Clone),
+        );
+        add_diff_assertion_bound(&mut bounds, &field.attrs, ty);
+        if field.attrs.is_copy_diff() {
+            bounds.add(ty, quote!(::derive_patch::patchable::PatchableField));
+        }
+        if field.attrs.nested.is_some() {
+            bounds.add(ty, quote!(::derive_patch::traits::PatchableExt));
+        }
+    }
+    let where_clause = bounds.where_clause();
+    // generic fields are checked by the bounds instead
+    let diff_assertions = diff_assertions(
+        patch_variants
+            .iter()
+            .flat_map(|v| &v.fields)
+            .filter(|f| !bounds.is_generic(f.ty))
+            .filter_map(|f| diff_assertion(&f.attrs, f.ty))
+            .collect(),
+    );
+
+    let declarations = patch_variants.iter().map(|v| {
+        let ident = v.ident;
+        let doc = format!("Changes the fields of `{}::{}`.", name, ident);
+        let fields = v.idents();
+        let diffs = v.diffs();
+        let forwarded = v.fields.iter().map(|f| &f.forwarded);
+        let field_docs = fields
+            .iter()
+            .map(|field| format!("The change of `{}::{}::{}`.", name, ident, field));
+        if v.is_unit {
+            quote!(#[doc = #doc] #ident)
+        } else {
+            quote! {
+                #[doc = #doc]
+                #ident {
+                    #(
+                        #[doc = #field_docs]
+                        #forwarded
+                        #fields: ::std::option::Option<#diffs>,
+                    )*
+                }
+            }
+        }
+    });
+
+    let variant_idents: Vec<&Ident> = patch_variants.iter().map(|v| v.ident).collect();
+    let variant_names: Vec<String> = variant_idents.iter().map(|v| v.to_string()).collect();
+    let idents: Vec<Vec<&Ident>> = patch_variants.iter().map(|v| v.idents()).collect();
+    let self_patterns: Vec<TokenStream> =
+        patch_variants.iter().map(|v| v.pattern("self")).collect();
+    let obj_patterns: Vec<TokenStream> = patch_variants.iter().map(|v| v.pattern("obj")).collect();
+    let self_bindings: Vec<Vec<Ident>> =
+        patch_variants.iter().map(|v| v.bindings("self")).collect();
+    let obj_bindings: Vec<Vec<Ident>> = patch_variants.iter().map(|v| v.bindings("obj")).collect();
+    let next_patterns: Vec<TokenStream> =
+        patch_variants.iter().map(|v| v.pattern("next")).collect();
+    let next_bindings: Vec<Vec<Ident>> =
+        patch_variants.iter().map(|v| v.bindings("next")).collect();
+    let target_patterns: Vec<TokenStream> =
+        patch_variants.iter().map(|v| v.pattern("target")).collect();
+    let target_bindings: Vec<Vec<Ident>> = patch_variants
+        .iter()
+        .map(|v| v.bindings("target"))
+        .collect();
+    let other_patterns: Vec<TokenStream> =
+        patch_variants.iter().map(|v| v.pattern("other")).collect();
+    let other_bindings: Vec<Vec<Ident>> =
+        patch_variants.iter().map(|v| v.bindings("other")).collect();
+    let diffs: Vec<Vec<TokenStream>> = patch_variants.iter().map(|v| v.diffs()).collect();
+    let field_names: Vec<Vec<String>> = idents
+        .iter()
+        .map(|fields| fields.iter().map(|f| f.to_string()).collect())
+        .collect();
+    let resolve_fns: Vec<Vec<TokenStream>> = patch_variants
+        .iter()
+        .map(|v| v.fields.iter().map(|f| f.attrs.resolve_fn()).collect())
+        .collect();
+
+    // see the invert bounds of `patch::derive`
+    let invert_bounds = diffs
+        .iter()
+        .flatten()
+        .map(|diff| quote!(for<'__invert> #diff: ::derive_patch::diff::Invert));
+    let invert_where_clause = bounds.where_clause_with(invert_bounds);
+
+    let max_fields = patch_variants
+        .iter()
+        .map(|v| v.fields.len())
+        .max()
+        .unwrap_or(0);
+    let is_complete = self_bindings
+        .iter()
+        .map(|fields| chain_or_true(fields.iter().map(|f| quote!(#f.is_some()))));
+    let is_empty = self_bindings
+        .iter()
+        .map(|fields| chain_or_true(fields.iter().map(|f| quote!(#f.is_none()))));
+    let count = self_bindings
+        .iter()
+        .map(|fields| sum_or_zero(fields.iter().map(|f| quote!(#f.is_some() as u32))));
+    let same_fields = self_bindings.iter().zip(&next_bindings).map(|(lhs, rhs)| {
+        chain_or_true(
+            lhs.iter()
+                .zip(rhs)
+                .map(|(lhs, rhs)| quote!(#lhs.is_some() == #rhs.is_some())),
+        )
+    });
+
+    let get_patch = patch_variants.iter().map(|v| {
+        let ident = v.ident;
+        let fields = v.idents();
+        let diffs = v.fields.iter().zip(v.diffs()).map(|(f, diff)| {
+            let old = binding("old", f.ident);
+            let new = binding("new", f.ident);
+            diff_or_none(&diff, quote!(#old), quote!(#new))
+        });
+        let old = v.pattern("old");
+        let new = v.pattern("new");
+        quote! {
+            (#name::#old, #name::#new) => #patch_name::#ident {
+                #(#fields: #diffs,)*
+            },
+        }
+    });
+
+    let from_partial = patch_variants.iter().map(|v| {
+        let ident = v.ident;
+        let in_partial: Vec<&PatchField> = v.fields.iter().filter(|f| f.in_partial).collect();
+        let partial_fields = in_partial.iter().map(|f| f.ident);
+        let partial_bindings = in_partial.iter().map(|f| binding("partial", f.ident));
+        let fields = v.idents();
+        let values = v.fields.iter().zip(v.diffs()).map(|(f, diff)| {
+            if f.in_partial {
+                let obj = binding("obj", f.ident);
+                let partial = binding("partial", f.ident);
+                let changed = diff_or_none(&diff, quote!(#obj), quote!(value));
+                quote! {
+                    match #partial {
+                        ::std::option::Option::Some(value) => #changed,
+                        ::std::option::Option::None => ::std::option::Option::None,
+                    }
+                }
+            } else {
+                quote!(::std::option::Option::None)
+            }
+        });
+        let obj = v.pattern("obj");
+        quote! {
+            (
+                #name::#obj,
+                #partial_name::#ident { #(#partial_fields: #partial_bindings,)* .. },
+            ) => #patch_name::#ident {
+                #(#fields: #values,)*
+            },
+        }
+    });
+
+    // the name of the variant of the patch `patch`
+    let variant_name = |patch: TokenStream| {
+        quote! {
+            match #patch {
+                #patch_name::#replace(_) => #REPLACE,
+                #(#patch_name::#variant_idents { .. } => #variant_names,)*
+            }
+        }
+    };
+    let self_variant_name = variant_name(quote!(self));
+    let target_variant_name = variant_name(quote!(target));
+    let clone_patch = |patch: TokenStream, patterns: &[TokenStream], bindings: &[Vec<Ident>]| {
+        quote! {
+            match #patch {
+                #patch_name::#replace(diff) => #patch_name::#replace(::std::clone::Clone::clone(diff)),
+                #(
+                    #patch_name::#patterns => #patch_name::#variant_idents {
+                        #(#idents: ::std::clone::Clone::clone(#bindings),)*
+                    },
+                )*
+            }
+        }
+    };
+    let clone_self = clone_patch(quote!(self), &self_patterns, &self_bindings);
+    let clone_other = clone_patch(quote!(other), &other_patterns, &other_bindings);
+    // the mismatches of `e` as merge conflicts
+    let add_conflicts = quote! {
+        for mismatch in e.mismatches() {
+            let mut conflict = ::std::clone::Clone::clone(mismatch);
+            conflict.set_mismatch_type(::derive_patch::mismatch::MismatchType::MergeConflict);
+            error.add_error(conflict);
+        }
+    };
+
+    let struct_doc = format!(
+        "A patch for `{}`.\n\nEither changes fields of the current variant, or replaces the whole object.",
+        name
+    );
+    let replace_doc = format!(
+        "Replaces the whole `{}`, used if the variant changes.",
+        name
+    );
+    let max_fields_doc = "The number of fields of the largest variant.";
+    let name_string = name.to_string();
+    let replace_name = REPLACE;
+
+    let type_attrs = struct_attrs.type_attrs();
+
+    Ok(quote! {
+        impl #impl_generics ::derive_patch::patchable::PatchableField for #name_ty #where_clause {}
+
+        impl #impl_generics ::derive_patch::traits::PatchableExt for #name_ty #where_clause {
+            type Patch = #patch_ty;
+
+            fn get_patch(&self, rhs: &Self) -> #patch_ty {
+                #[allow(unreachable_patterns)]
+                match (self, rhs) {
+                    #(#get_patch)*
+                    (old, new) => #patch_name::#replace(::derive_patch::diff::Diff::new(old, new)),
+                }
+            }
+        }
+
+        #[doc = #struct_doc]
+        #[derive(PartialEq, Debug, Clone)]
+        #type_attrs
+        #vis enum #patch_name #generics #where_clause {
+            #[doc = #replace_doc]
+            #replace(::derive_patch::diff::CopyDiff<#name_ty>),
+            #(#declarations,)*
+        }
+
+        impl #impl_generics #patch_ty #where_clause {
+            /// Generates a patch that changes `obj` to the values set in
+            /// `partial`.
+            ///
+            /// Fields of `partial` that equal the current value of `obj` are
+            /// left out. If `partial` is another variant than `obj`, the patch
+            /// replaces `obj`, which fails if `partial` is not complete.
+            pub fn new_with_partial(
+                obj: &#name_ty,
+                partial: &#partial_ty,
+            ) -> ::std::result::Result<
+                Self,
+                ::derive_patch::mismatch::IncompleteError<#partial_ty>,
+            > {
+                #[allow(unreachable_patterns)]
+                let patch = match (obj, partial) {
+                    #(#from_partial)*
+                    (obj, partial) => match ::derive_patch::traits::Partial::build(partial) {
+                        ::std::result::Result::Ok(value) => {
+                            #patch_name::#replace(::derive_patch::diff::Diff::new(obj, &value))
+                        }
+                        ::std::result::Result::Err(()) => {
+                            return ::std::result::Result::Err(
+                                ::derive_patch::mismatch::IncompleteError::new(
+                                    "new_with_partial",
+                                    #name_string,
+                                    partial.clone(),
+                                ),
+                            );
+                        }
+                    },
+                };
+                ::std::result::Result::Ok(patch)
+            }
+
+            /// internal apply function. Responsible for actually assigning the
+            /// values.
+            ///
+            /// This function performs no checks, it's the responsibility of the
+            /// caller to perform checks.
+            ///
+            /// Used by `apply`, after `can_apply_cleanly` succeeded.
+            fn _apply(&self, obj: &mut #name_ty) {
+                #[allow(unreachable_patterns)]
+                match (self, obj) {
+                    (#patch_name::#replace(diff), obj) => {
+                        let applied = ::derive_patch::diff::Diff::apply_into(diff, obj);
+                        ::std::debug_assert!(applied.is_ok(), "the diff was checked before");
+                    }
+                    #(
+                        (#patch_name::#self_patterns, #name::#obj_patterns) => {
+                            #(
+                                if let ::std::option::Option::Some(diff) = #self_bindings {
+                                    let applied = ::derive_patch::diff::Diff::apply_into(diff, #obj_bindings);
+                                    ::std::debug_assert!(applied.is_ok(), "the diff was checked before");
+                                }
+                            )*
+                        }
+                    )*
+                    _ => {}
+                }
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Base<::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError>>
+            for #patch_ty
+            #where_clause
+        {
+            type Target = #name_ty;
+
+            #[doc = #max_fields_doc]
+            const MAX_FIELDS: usize = #max_fields;
+
+            /// returns true if ALL fields of the variant have an associated
+            /// diff, or if the patch replaces the whole object.
+            fn is_complete(&self) -> bool {
+                match self {
+                    #patch_name::#replace(_) => true,
+                    #(#patch_name::#self_patterns => #is_complete,)*
+                }
+            }
+
+            fn is_empty(&self) -> bool {
+                match self {
+                    #patch_name::#replace(diff) => !::derive_patch::diff::Diff::contains_change(diff),
+                    #(#patch_name::#self_patterns => #is_empty,)*
+                }
+            }
+
+            fn count(&self) -> u32 {
+                match self {
+                    #patch_name::#replace(diff) => ::derive_patch::diff::Diff::contains_change(diff) as u32,
+                    #(#patch_name::#self_patterns => #count,)*
+                }
+            }
+
+            /// applies the patch to the given object
+            ///
+            /// makes sure that the object is the variant of the patch and that
+            /// all old_values of the patches fields are the current values of
+            /// the given object. Returns an Error otherwise.
+            ///
+            /// This function does not change `obj` if an Error occurs.
+            fn apply(
+                &self,
+                obj: &mut #name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                ::derive_patch::traits::Patch::is_correct_target(self, obj)?;
+                ::derive_patch::traits::Patch::can_apply_cleanly(self, obj)?;
+
+                self._apply(obj);
+
+                ::std::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::Patch for #patch_ty #where_clause {
+            fn cleanup(&mut self) -> bool {
+                let mut changed = false;
+                match self {
+                    #patch_name::#replace(_) => {}
+                    #(
+                        #patch_name::#self_patterns => {
+                            #(
+                                if let ::std::option::Option::Some(diff) = #self_bindings {
+                                    if ::derive_patch::diff::Diff::cleanup(diff) {
+                                        changed = true;
+                                    }
+                                    if !::derive_patch::diff::Diff::contains_change(diff) {
+                                        *#self_bindings = ::std::option::Option::None;
+                                        changed = true;
+                                    }
+                                }
+                            )*
+                        }
+                    )*
+                }
+                changed
+            }
+
+            fn is_correct_target(
+                &self,
+                _obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                ::std::result::Result::Ok(())
+            }
+
+            /// Mismatches of the whole object, e.g. of the variant, have an
+            /// empty field name. A parent patch fills in its field.
+            fn can_apply_cleanly(
+                &self,
+                obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+
+                #[allow(unreachable_patterns)]
+                match (self, obj) {
+                    (#patch_name::#replace(diff), obj) => {
+                        if let ::std::result::Result::Err(e) =
+                            <::derive_patch::diff::CopyDiff<#name_ty> as ::derive_patch::diff::Diff>::check(diff, obj)
+                        {
+                            error.merge(&e);
+                        }
+                    }
+                    #(
+                        (#patch_name::#self_patterns, #name::#obj_patterns) => {
+                            #(
+                                if let ::std::option::Option::Some(diff) = #self_bindings {
+                                    if let ::std::result::Result::Err(mut e) =
+                                        <#diffs as ::derive_patch::diff::Diff>::check(diff, #obj_bindings)
+                                    {
+                                        e.prefix_fields(#field_names);
+                                        error.merge(&e);
+                                    }
+                                }
+                            )*
+                        }
+                    )*
+                    (patch, obj) => {
+                        let expected = match patch {
+                            #patch_name::#replace(_) => #replace_name,
+                            #(#patch_name::#variant_idents { .. } => #variant_names,)*
+                        };
+                        let received = match obj {
+                            #(#name::#variant_idents { .. } => #variant_names,)*
+                        };
+                        error.add_error(::derive_patch::mismatch::MismatchError::new(
+                            "",
+                            expected,
+                            received,
+                            ::derive_patch::mismatch::MismatchType::VariantMismatch,
+                        ));
+                    }
+                }
+
+                if error.is_error_free() {
+                    ::std::result::Result::Ok(())
+                } else {
+                    ::std::result::Result::Err(error)
+                }
+            }
+
+            fn is_same_target(&self, _other: &Self) -> bool {
+                true
+            }
+
+            fn coalesce(&mut self, next: &Self) -> bool {
+                #[allow(unreachable_patterns)]
+                let coalesced = match (&*self, next) {
+                    (#patch_name::#replace(lhs), #patch_name::#replace(rhs)) => {
+                        let mut lhs = ::std::clone::Clone::clone(lhs);
+                        if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                            return false;
+                        }
+                        #patch_name::#replace(lhs)
+                    }
+                    #(
+                        (#patch_name::#self_patterns, #patch_name::#next_patterns) => {
+                            if !(#same_fields) {
+                                return false;
+                            }
+                            #patch_name::#variant_idents {
+                                #(
+                                    #idents: match (#self_bindings, #next_bindings) {
+                                        (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                                            let mut lhs = ::std::clone::Clone::clone(lhs);
+                                            if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                                                return false;
+                                            }
+                                            ::std::option::Option::Some(lhs)
+                                        }
+                                        _ => ::std::option::Option::None,
+                                    },
+                                )*
+                            }
+                        }
+                    )*
+                    _ => return false,
+                };
+                *self = coalesced;
+                true
+            }
+
+            fn merge_into(
+                &self,
+                target: &mut Self,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+                #[allow(unreachable_patterns)]
+                let merged = match (&*target, self) {
+                    (#patch_name::#replace(lhs), #patch_name::#replace(rhs)) => {
+                        let mut lhs = ::std::clone::Clone::clone(lhs);
+                        if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                            error.add_error(::derive_patch::mismatch::MismatchError::new(
+                                "",
+                                ::std::format!("{:?}", lhs.new_value()),
+                                ::std::format!("{:?}", rhs.old_value()),
+                                ::derive_patch::mismatch::MismatchType::MergeConflict,
+                            ));
+                        }
+                        #patch_name::#replace(lhs)
+                    }
+                    (#patch_name::#replace(lhs), rhs) => {
+                        // the value the first patch sets is known
+                        let mut value = ::std::clone::Clone::clone(lhs.new_value());
+                        if let ::std::result::Result::Err(e) =
+                            ::derive_patch::traits::Base::apply(rhs, &mut value)
+                        {
+                            #add_conflicts
+                        }
+                        #patch_name::#replace(::derive_patch::diff::Diff::new(lhs.old_value(), &value))
+                    }
+                    (_, #patch_name::#replace(_)) => {
+                        // the value in front of the first patch is not known,
+                        // it would need the diffs of the fields to be inverted
+                        error.add_error(::derive_patch::mismatch::MismatchError::new(
+                            "",
+                            #target_variant_name,
+                            "a replacement of the whole value",
+                            ::derive_patch::mismatch::MismatchType::MergeConflict,
+                        ));
+                        return ::std::result::Result::Err(error);
+                    }
+                    #(
+                        (#patch_name::#target_patterns, #patch_name::#self_patterns) => {
+                            #patch_name::#variant_idents {
+                                #(
+                                    #idents: match (#target_bindings, #self_bindings) {
+                                        (::std::option::Option::Some(lhs), ::std::option::Option::Some(rhs)) => {
+                                            let mut lhs = ::std::clone::Clone::clone(lhs);
+                                            if ::derive_patch::diff::Diff::merge(&mut lhs, rhs).is_err() {
+                                                error.add_error(::derive_patch::mismatch::MismatchError::new(
+                                                    #field_names,
+                                                    "a change of the merged value",
+                                                    "a conflicting change",
+                                                    ::derive_patch::mismatch::MismatchType::MergeConflict,
+                                                ));
+                                            }
+                                            ::std::option::Option::Some(lhs)
+                                        }
+                                        (lhs, rhs) => ::std::option::Option::or(lhs.as_ref(), rhs.as_ref()).cloned(),
+                                    },
+                                )*
+                            }
+                        }
+                    )*
+                    (target, _) => {
+                        error.add_error(::derive_patch::mismatch::MismatchError::new(
+                            "",
+                            #target_variant_name,
+                            #self_variant_name,
+                            ::derive_patch::mismatch::MismatchType::VariantMismatch,
+                        ));
+                        return ::std::result::Result::Err(error);
+                    }
+                };
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+                *target = merged;
+                ::std::result::Result::Ok(())
+            }
+
+            /// Fields of the same variant are overwritten one by one, a patch
+            /// of another variant replaces `target`.
+            fn overwrite_into(
+                &self,
+                target: &mut Self,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                #[allow(unreachable_patterns)]
+                match (&mut *target, self) {
+                    #(
+                        (#patch_name::#target_patterns, #patch_name::#self_patterns) => {
+                            #(
+                                if let ::std::option::Option::Some(diff) = #self_bindings {
+                                    *#target_bindings = ::std::option::Option::Some(::std::clone::Clone::clone(diff));
+                                }
+                            )*
+                        }
+                    )*
+                    (target, _) => *target = #clone_self,
+                }
+                ::std::result::Result::Ok(())
+            }
+
+            fn rebase(
+                &mut self,
+                obj: &#name_ty,
+            ) -> ::std::result::Result<(), ::derive_patch::mismatch::MultipleMismatchError> {
+                let mut error = ::derive_patch::mismatch::MultipleMismatchError::new();
+                #[allow(unreachable_patterns)]
+                let rebased = match (&*self, obj) {
+                    (#patch_name::#replace(diff), obj) => {
+                        let mut diff = ::std::clone::Clone::clone(diff);
+                        if let ::std::result::Result::Err(e) = ::derive_patch::diff::Diff::rebase(&mut diff, obj) {
+                            error.merge(&e);
+                        }
+                        #patch_name::#replace(diff)
+                    }
+                    #(
+                        (#patch_name::#self_patterns, #name::#obj_patterns) => {
+                            #patch_name::#variant_idents {
+                                #(
+                                    #idents: match #self_bindings {
+                                        ::std::option::Option::Some(diff) => {
+                                            let mut diff = ::std::clone::Clone::clone(diff);
+                                            if let ::std::result::Result::Err(mut e) =
+                                                <#diffs as ::derive_patch::diff::Diff>::rebase(&mut diff, #obj_bindings)
+                                            {
+                                                e.prefix_fields(#field_names);
+                                                error.merge(&e);
+                                            }
+                                            ::std::option::Option::Some(diff)
+                                        }
+                                        ::std::option::Option::None => ::std::option::Option::None,
+                                    },
+                                )*
+                            }
+                        }
+                    )*
+                    // reports the variant mismatch
+                    _ => return ::derive_patch::traits::Patch::can_apply_cleanly(self, obj),
+                };
+                if error.has_errors() {
+                    return ::std::result::Result::Err(error);
+                }
+                *self = rebased;
+                ::std::result::Result::Ok(())
+            }
+
+            fn merge_concurrent<R>(
+                &self,
+                other: &Self,
+                resolver: &mut R,
+            ) -> ::std::result::Result<Self, ::std::vec::Vec<::derive_patch::conflict::Conflict>>
+            where
+                R: ::derive_patch::conflict::ConflictResolver + ?::std::marker::Sized,
+            {
+                let mut conflicts = ::std::vec::Vec::new();
+                #[allow(unreachable_patterns)]
+                let merged = match (self, other) {
+                    (#patch_name::#replace(ours), #patch_name::#replace(theirs)) => {
+                        match ::derive_patch::conflict::resolve_field("", ours, theirs, resolver, &mut conflicts) {
+                            ::std::option::Option::Some(diff) => #patch_name::#replace(diff),
+                            ::std::option::Option::None => return ::std::result::Result::Err(conflicts),
+                        }
+                    }
+                    #(
+                        (#patch_name::#self_patterns, #patch_name::#other_patterns) => {
+                            #patch_name::#variant_idents {
+                                #(
+                                    #idents: match (#self_bindings, #other_bindings) {
+                                        (::std::option::Option::Some(ours), ::std::option::Option::Some(theirs)) => {
+                                            #resolve_fns(
+                                                #field_names,
+                                                ours,
+                                                theirs,
+                                                resolver,
+                                                &mut conflicts,
+                                            )
+                                        }
+                                        (ours, theirs) => ::std::option::Option::or(ours.as_ref(), theirs.as_ref()).cloned(),
+                                    },
+                                )*
+                            }
+                        }
+                    )*
+                    // other variants, or a replacement and a change of a variant
+                    _ => {
+                        let conflict = ::derive_patch::conflict::Conflict::new(
+                            "",
+                            ::derive_patch::mismatch::MismatchType::VariantMismatch,
+                            false,
+                        );
+                        match resolver.resolve(&conflict) {
+                            ::derive_patch::conflict::Resolution::Ours => #clone_self,
+                            ::derive_patch::conflict::Resolution::Theirs => #clone_other,
+                            _ => return ::std::result::Result::Err(::std::vec![conflict]),
+                        }
+                    }
+                };
+                if !conflicts.is_empty() {
+                    return ::std::result::Result::Err(conflicts);
+                }
+                ::std::result::Result::Ok(merged)
+            }
+        }
+
+        impl #impl_generics ::derive_patch::traits::InvertPatch for #patch_ty #invert_where_clause {
+            fn invert(&self) -> Self {
+                match self {
+                    #patch_name::#replace(diff) => {
+                        #patch_name::#replace(::derive_patch::diff::Invert::invert(diff))
+                    }
+                    #(
+                        #patch_name::#self_patterns => #patch_name::#variant_idents {
+                            #(#idents: #self_bindings.as_ref().map(::derive_patch::diff::Invert::invert),)*
+                        },
+                    )*
+                }
+            }
+        }
+
+        #diff_assertions
+    })
+}
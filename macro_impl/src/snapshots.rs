@@ -0,0 +1,73 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Snapshots of the code the derives generate for the `Example` structs of
+//! tests/template_partial.rs and tests/template_patch.rs.
+//!
+//! The expansion is pretty printed and compared against the files in
+//! macro_impl/snapshots, so every change to the generated code shows up in
+//! review. Run with `UPDATE_SNAPSHOTS=1` to accept the new expansion. The
+//! snapshots are taken without the `serde` feature.
+
+use crate::{partial, patch};
+use proc_macro2::TokenStream;
+use std::{env, fs, path::Path};
+use syn::{DeriveInput, Item, Result};
+
+/// The `Example` struct of the template file at `template`, relative to the
+/// root of the workspace.
+fn example(template: &str) -> DeriveInput {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(template);
+    let source = fs::read_to_string(&path).expect("template file");
+    let file = syn::parse_file(&source).expect("template file is valid rust");
+    let example = file
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::Struct(item) if item.ident == "Example" => Some(item),
+            _ => None,
+        })
+        .expect("template file contains `Example`");
+    DeriveInput::from(example)
+}
+
+/// Compares the pretty printed `expansion` against the snapshot `name`.
+fn assert_snapshot(name: &str, expansion: Result<TokenStream>) {
+    let expansion = expansion.expect("derive succeeds");
+    let file = syn::parse2(expansion).expect("expansion is valid rust");
+    let actual = prettyplease::unparse(&file);
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(name);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).expect("snapshot is writable");
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "the expansion differs from {}, run with UPDATE_SNAPSHOTS=1 to accept it:\n{}",
+        path.display(),
+        actual
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "serde", ignore = "the snapshots are taken without serde")]
+fn partial_example() {
+    let input = example("tests/template_partial.rs");
+    assert_snapshot("partial_example.rs", partial::derive(&input));
+}
+
+#[test]
+#[cfg_attr(feature = "serde", ignore = "the snapshots are taken without serde")]
+fn patch_example() {
+    let input = example("tests/template_patch.rs");
+    assert_snapshot("patch_example.rs", patch::derive(&input));
+}
@@ -13,7 +13,7 @@ pub(crate) mod tests {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// #[test]
     /// fn test_send() {
     ///     fn assert_send<T: Send>() {}
@@ -31,7 +31,7 @@ pub(crate) mod tests {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// #[test]
     /// fn test_send() {
     ///     fn assert_send<T: Send>() {}
@@ -47,13 +47,13 @@ pub(crate) mod tests {
 
     #[test]
     fn test_sync() {
-        struct ShouldBeSyncable {};
+        struct ShouldBeSyncable {}
         assert_sync::<ShouldBeSyncable>();
     }
 
     #[test]
     fn test_send() {
-        struct ShouldBeSendable {};
+        struct ShouldBeSendable {}
         assert_send::<ShouldBeSendable>();
     }
 }
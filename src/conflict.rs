@@ -0,0 +1,262 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Resolving the conflicts of two patches made concurrently from the same
+//! object, see `Patch::merge_concurrent`.
+
+use crate::{
+    diff::{Diff, NestedPatchDiff},
+    mismatch::MismatchType,
+    traits::{Patch, PatchableExt},
+};
+use std::fmt;
+
+/// A field both patches change, or a difference of the patches that can not
+/// be merged at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    field: String,
+    mismatch_type: MismatchType,
+    combinable: bool,
+}
+
+impl Conflict {
+    /// Creates a new conflict of `field`.
+    pub fn new<N>(field: N, mismatch_type: MismatchType, combinable: bool) -> Conflict
+    where
+        N: Into<String>,
+    {
+        Conflict {
+            field: field.into(),
+            mismatch_type,
+            combinable,
+        }
+    }
+
+    /// The name of the field, empty if the conflict is about the whole
+    /// object, e.g. for two patches of different variants. Fields of nested
+    /// patches are separated by dots, e.g. `address.city`.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Puts `prefix` in front of the field name, separated by a dot, like
+    /// `MismatchError::prefix_name`. An empty name becomes `prefix`.
+    pub fn prefix_name(&mut self, prefix: &str) {
+        self.field = if self.field.is_empty() {
+            prefix.to_string()
+        } else {
+            format!("{}.{}", prefix, self.field)
+        };
+    }
+
+    /// `MergeConflict` if both patches change the field,
+    /// `VariantMismatch` if they change different variants of an enum and
+    /// `ObjectID` if they have different targets. Conflicts of id fields are
+    /// never passed to a `ConflictResolver`.
+    pub fn mismatch_type(&self) -> MismatchType {
+        self.mismatch_type
+    }
+
+    /// True if both changes can be combined, see `Diff::combine`.
+    pub fn can_combine(&self) -> bool {
+        self.combinable
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} - field {}", self.mismatch_type, self.field)
+    }
+}
+
+/// The way a `ConflictResolver` resolves a conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the change of the patch `merge_concurrent` is called on.
+    Ours,
+    /// Keep the change of the other patch.
+    Theirs,
+    /// Make both changes, see `Diff::combine`. The conflict stays unresolved
+    /// if they can not be combined.
+    Combine,
+    /// Leave the conflict unresolved.
+    Unresolved,
+}
+
+/// Decides how the fields that both patches of `Patch::merge_concurrent`
+/// change are merged.
+///
+/// Closures `FnMut(&Conflict) -> Resolution` are resolvers as well, e.g. to
+/// decide per field.
+pub trait ConflictResolver {
+    /// Resolves a single conflict.
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution;
+}
+
+impl<F> ConflictResolver for F
+where
+    F: FnMut(&Conflict) -> Resolution,
+{
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution {
+        self(conflict)
+    }
+}
+
+/// Keeps the changes of the patch `merge_concurrent` is called on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OursWins;
+
+impl ConflictResolver for OursWins {
+    fn resolve(&mut self, _conflict: &Conflict) -> Resolution {
+        Resolution::Ours
+    }
+}
+
+/// Keeps the changes of the other patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TheirsWins;
+
+impl ConflictResolver for TheirsWins {
+    fn resolve(&mut self, _conflict: &Conflict) -> Resolution {
+        Resolution::Theirs
+    }
+}
+
+/// Combines both changes, e.g. adds up the differences of a
+/// `NumericDistanceDiff`.
+///
+/// Conflicts of diffs that can not be combined stay unresolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sum;
+
+impl ConflictResolver for Sum {
+    fn resolve(&mut self, _conflict: &Conflict) -> Resolution {
+        Resolution::Combine
+    }
+}
+
+/// Resolves the conflict of `field`, which both `ours` and `theirs` change.
+///
+/// Returns the diff of the merged patch, or `None` and adds the conflict to
+/// `conflicts` if it stays unresolved. Used by the generated
+/// `merge_concurrent`.
+pub fn resolve_field<D, R>(
+    field: &str,
+    ours: &D,
+    theirs: &D,
+    resolver: &mut R,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<D>
+where
+    D: Diff + Clone,
+    R: ConflictResolver + ?Sized,
+{
+    let combined = ours.combine(theirs);
+    let conflict = Conflict::new(field, MismatchType::MergeConflict, combined.is_some());
+    match resolver.resolve(&conflict) {
+        Resolution::Ours => Some(ours.clone()),
+        Resolution::Theirs => Some(theirs.clone()),
+        Resolution::Combine if combined.is_some() => combined,
+        _ => {
+            conflicts.push(conflict);
+            None
+        }
+    }
+}
+
+/// Passes the conflicts of a nested patch to the resolver of the outer one,
+/// named with the path to the nested field.
+struct NestedResolver<'a, R: ?Sized> {
+    field: &'a str,
+    resolver: &'a mut R,
+}
+
+impl<R> ConflictResolver for NestedResolver<'_, R>
+where
+    R: ConflictResolver + ?Sized,
+{
+    fn resolve(&mut self, conflict: &Conflict) -> Resolution {
+        let mut conflict = conflict.clone();
+        conflict.prefix_name(self.field);
+        self.resolver.resolve(&conflict)
+    }
+}
+
+/// Resolves the conflict of the nested `field`, which both `ours` and
+/// `theirs` change, like `resolve_field`.
+///
+/// Merges the nested patches with `Patch::merge_concurrent`, so the fields of
+/// the nested struct that only one of them changes are kept and `resolver`
+/// decides on the others, e.g. `address.city`. Used by the generated
+/// `merge_concurrent` for `#[patch(nested)]` fields.
+pub fn resolve_nested<T, R>(
+    field: &str,
+    ours: &NestedPatchDiff<T>,
+    theirs: &NestedPatchDiff<T>,
+    resolver: &mut R,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<NestedPatchDiff<T>>
+where
+    T: PatchableExt,
+    R: ConflictResolver + ?Sized,
+{
+    let mut nested = NestedResolver { field, resolver };
+    match ours.patch().merge_concurrent(theirs.patch(), &mut nested) {
+        Ok(patch) => Some(NestedPatchDiff::from_patch(patch)),
+        Err(nested) => {
+            conflicts.extend(nested.into_iter().map(|mut conflict| {
+                conflict.prefix_name(field);
+                conflict
+            }));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{CopyDiff, NumericDistanceDiff};
+
+    #[test]
+    fn test_resolve_field() {
+        let ours: NumericDistanceDiff<i32> = Diff::new(&0, &2);
+        let theirs: NumericDistanceDiff<i32> = Diff::new(&0, &3);
+        let mut conflicts = Vec::new();
+
+        let sum = resolve_field("n", &ours, &theirs, &mut Sum, &mut conflicts);
+        assert_eq!(sum, Some(Diff::new(&0, &5)));
+        let theirs_wins = resolve_field("n", &ours, &theirs, &mut TheirsWins, &mut conflicts);
+        assert_eq!(theirs_wins, Some(theirs));
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_unresolved() {
+        let ours: CopyDiff<u8> = Diff::new(&0, &1);
+        let theirs: CopyDiff<u8> = Diff::new(&0, &2);
+        let mut conflicts = Vec::new();
+
+        assert_eq!(
+            resolve_field("n", &ours, &theirs, &mut Sum, &mut conflicts),
+            None
+        );
+        let mut never = |_: &Conflict| Resolution::Unresolved;
+        assert_eq!(
+            resolve_field("m", &ours, &ours, &mut never, &mut conflicts),
+            None
+        );
+
+        assert_eq!(
+            conflicts,
+            [
+                Conflict::new("n", MismatchType::MergeConflict, false),
+                Conflict::new("m", MismatchType::MergeConflict, true),
+            ]
+        );
+    }
+}
@@ -5,8 +5,8 @@
  */
 
 use crate::{
-    diff::Diff,
-    mismatch::{MismatchError, MismatchType},
+    diff::{Diff, Invert, Priority, Transform},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
     patchable::PatchableField,
 };
 
@@ -30,6 +30,21 @@ where
     old_value: T,
 }
 
+impl<T> CopyDiff<T>
+where
+    T: PatchableField,
+{
+    /// The value the object has to have before the diff is applied.
+    pub fn old_value(&self) -> &T {
+        &self.old_value
+    }
+
+    /// The value of the object after the diff is applied.
+    pub fn new_value(&self) -> &T {
+        &self.new_value
+    }
+}
+
 impl<T> Diff for CopyDiff<T>
 where
     T: Clone + std::cmp::PartialEq + PatchableField + std::fmt::Debug,
@@ -72,6 +87,24 @@ where
         }
     }
 
+    /// Sets the old value to `obj`, never fails.
+    fn rebase(&mut self, obj: &T) -> Result<(), MultipleMismatchError> {
+        self.old_value = obj.clone();
+        Ok(())
+    }
+
+    /// Only the same change can be combined, e.g. if both set the same new
+    /// value.
+    fn combine(&self, concurrent: &Self) -> Option<Self> {
+        if self.old_value.compare(&concurrent.old_value)
+            && self.new_value.compare(&concurrent.new_value)
+        {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+
     fn applies_cleanly(&self, obj: &T) -> Result<(), MismatchError>
     where
         T: std::fmt::Debug,
@@ -79,12 +112,13 @@ where
         if self.old_value.compare(obj) {
             Ok(())
         } else {
+            // the field name is filled in by the patch, see `MismatchError::prefix_name`
             Err(MismatchError::new(
-                "food",
+                "",
                 format!("{:?}", self.old_value),
                 format!("{:?}", obj),
                 MismatchType::PatchOldValue,
-            )) //todo:  better error handlings
+            ))
         }
     }
 
@@ -107,11 +141,43 @@ where
     }
 }
 
+impl<T> Invert for CopyDiff<T>
+where
+    T: Clone + std::cmp::PartialEq + PatchableField + std::fmt::Debug,
+{
+    /// Swaps the old and the new value.
+    fn invert(&self) -> Self {
+        CopyDiff {
+            old_value: self.new_value.clone(),
+            new_value: self.old_value.clone(),
+        }
+    }
+}
+
+impl<T> Transform for CopyDiff<T>
+where
+    T: Clone + std::cmp::PartialEq + PatchableField + std::fmt::Debug,
+{
+    /// Expects the value `concurrent` sets. With the priority, this diff
+    /// sets its value over it, otherwise it keeps the value of `concurrent`.
+    fn transform(&self, concurrent: &Self, priority: Priority) -> Self {
+        let new_value = match priority {
+            Priority::Ours => &self.new_value,
+            Priority::Theirs => &concurrent.new_value,
+        };
+        CopyDiff {
+            old_value: concurrent.new_value.clone(),
+            new_value: new_value.clone(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{assert::tests::*, patchable::PatchableField};
 
+    #[allow(dead_code)]
     struct PatchableStruct {
         pub d1: u64,
         pub d2: String,
@@ -0,0 +1,482 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use crate::{
+    diff::{CopyDiff, Diff, Invert},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
+    patchable::PatchableField,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The operations of a map a `MapDiff` needs.
+pub trait Map<K, V> {
+    /// The value of `key`, if there is one.
+    fn value(&self, key: &K) -> Option<&V>;
+
+    /// The value of `key` to change it, if there is one.
+    fn value_mut(&mut self, key: &K) -> Option<&mut V>;
+
+    /// Sets the value of `key`.
+    fn insert_value(&mut self, key: K, value: V);
+
+    /// Removes `key` and its value.
+    fn remove_value(&mut self, key: &K);
+
+    /// All keys and their values.
+    fn entries<'a>(&'a self) -> Box<dyn Iterator<Item = (&'a K, &'a V)> + 'a>;
+}
+
+impl<K, V> Map<K, V> for HashMap<K, V>
+where
+    K: Eq + Hash,
+{
+    fn value(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn value_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+
+    fn insert_value(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn remove_value(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    fn entries<'a>(&'a self) -> Box<dyn Iterator<Item = (&'a K, &'a V)> + 'a> {
+        Box::new(self.iter())
+    }
+}
+
+impl<K, V> Map<K, V> for BTreeMap<K, V>
+where
+    K: Ord,
+{
+    fn value(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn value_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+
+    fn insert_value(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn remove_value(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    fn entries<'a>(&'a self) -> Box<dyn Iterator<Item = (&'a K, &'a V)> + 'a> {
+        Box::new(self.iter())
+    }
+}
+
+/// The change of a single key of a `MapDiff`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MapChange<V, D> {
+    /// The key is added with this value, it must not exist before.
+    Insert(V),
+    /// The key is removed, its value has to be this one.
+    Remove(V),
+    /// The value of the key is changed by the inner diff.
+    Update(D),
+}
+
+/// Stores the changes of a map per key, instead of two copies of the whole
+/// map.
+///
+/// Keys are inserted, removed or updated. Updates use the inner `Diff` `D`
+/// for the value, which defaults to `CopyDiff<V>`. Only the changed keys are
+/// checked when the diff is applied, so changes to other keys never conflict.
+///
+/// `M` is the map, `HashMap<K, V>` by default, see `BTreeMapDiff` for a
+/// `BTreeMap`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: Serialize, V: Serialize, D: Serialize",
+        deserialize = "K: Deserialize<'de>, V: Deserialize<'de>, D: Deserialize<'de>"
+    ))
+)]
+pub struct MapDiff<K, V, D = CopyDiff<V>, M = HashMap<K, V>>
+where
+    V: PatchableField,
+{
+    changes: Vec<(K, MapChange<V, D>)>,
+    map: PhantomData<M>,
+}
+
+/// A `MapDiff` of a `BTreeMap`.
+pub type BTreeMapDiff<K, V, D = CopyDiff<V>> = MapDiff<K, V, D, BTreeMap<K, V>>;
+
+impl<K, V, D, M> MapDiff<K, V, D, M>
+where
+    K: PartialEq,
+    V: PatchableField,
+{
+    /// The changed keys with their change.
+    ///
+    /// Every key is only listed once. The order is the one of the map the
+    /// diff was created of.
+    pub fn changes(&self) -> &[(K, MapChange<V, D>)] {
+        &self.changes
+    }
+
+    /// The change of `key`, if it is changed.
+    pub fn change(&self, key: &K) -> Option<&MapChange<V, D>> {
+        self.changes
+            .iter()
+            .find(|(changed, _)| changed == key)
+            .map(|(_, change)| change)
+    }
+}
+
+impl<K, V, D, M> PartialEq for MapDiff<K, V, D, M>
+where
+    K: PartialEq,
+    V: PatchableField + PartialEq,
+    D: PartialEq,
+{
+    /// The order of the changes does not matter.
+    fn eq(&self, rhs: &Self) -> bool {
+        self.changes.len() == rhs.changes.len()
+            && self
+                .changes
+                .iter()
+                .all(|(key, change)| rhs.change(key) == Some(change))
+    }
+}
+
+impl<K, V, D, M> Diff for MapDiff<K, V, D, M>
+where
+    K: Clone + Debug + PartialEq,
+    V: Clone + Debug + PartialEq + PatchableField,
+    D: Diff<Object = V> + Clone,
+    M: Map<K, V>,
+{
+    type DiffResult = Vec<(K, MapChange<V, D>)>;
+    type Object = M;
+
+    fn new(old: &M, new: &M) -> Self {
+        let mut changes = Vec::new();
+        for (key, old_value) in old.entries() {
+            match new.value(key) {
+                None => changes.push((key.clone(), MapChange::Remove(old_value.clone()))),
+                Some(new_value) => {
+                    let diff = D::new(old_value, new_value);
+                    if diff.contains_change() {
+                        changes.push((key.clone(), MapChange::Update(diff)));
+                    }
+                }
+            }
+        }
+        for (key, new_value) in new.entries() {
+            if old.value(key).is_none() {
+                changes.push((key.clone(), MapChange::Insert(new_value.clone())));
+            }
+        }
+        MapDiff {
+            changes,
+            map: PhantomData,
+        }
+    }
+
+    fn changes_object(&self, old: &M) -> bool {
+        self.contains_change() && self.applies_cleanly(old).is_ok()
+    }
+
+    fn contains_change(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Combines the changes of both diffs key by key.
+    ///
+    /// Fails if both change the same key in a way that does not fit together,
+    /// e.g. `rhs` inserts a key this diff inserted already. An update that is
+    /// followed by a removal can not be merged either, the diff does not know
+    /// the value in front of the update.
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        let mut changes = self.changes.clone();
+        for (key, change) in &rhs.changes {
+            let position = changes.iter().position(|(changed, _)| changed == key);
+            let position = match position {
+                Some(position) => position,
+                None => {
+                    changes.push((key.clone(), change.clone()));
+                    continue;
+                }
+            };
+            let merged = match (&changes[position].1, change) {
+                (MapChange::Insert(value), MapChange::Update(diff)) => {
+                    let mut value = value.clone();
+                    diff.apply_into(&mut value).map_err(|_| ())?;
+                    Some(MapChange::Insert(value))
+                }
+                (MapChange::Insert(value), MapChange::Remove(removed))
+                    if value.compare(removed) =>
+                {
+                    None
+                }
+                (MapChange::Remove(removed), MapChange::Insert(value)) => {
+                    let diff = D::new(removed, value);
+                    if diff.contains_change() {
+                        Some(MapChange::Update(diff))
+                    } else {
+                        None
+                    }
+                }
+                (MapChange::Update(diff), MapChange::Update(rhs)) => {
+                    let mut diff = diff.clone();
+                    diff.merge(rhs)?;
+                    Some(MapChange::Update(diff))
+                }
+                _ => return Err(()),
+            };
+            match merged {
+                Some(merged) => changes[position].1 = merged,
+                None => {
+                    changes.remove(position);
+                }
+            }
+        }
+        self.changes = changes;
+        Ok(())
+    }
+
+    /// Rebases every change onto the value of its key in `obj`.
+    ///
+    /// An insertion of a key that exists becomes an update, a removal takes
+    /// the current value and is dropped if the key does not exist. Updates
+    /// are rebased by the inner diff, they fail if the key does not exist.
+    fn rebase(&mut self, obj: &M) -> Result<(), MultipleMismatchError> {
+        let mut error = MultipleMismatchError::new();
+        let mut changes = Vec::new();
+        for (key, change) in &self.changes {
+            let change = match (change, obj.value(key)) {
+                (MapChange::Insert(value), Some(current)) => {
+                    Some(MapChange::Update(D::new(current, value)))
+                }
+                (MapChange::Remove(_), Some(current)) => Some(MapChange::Remove(current.clone())),
+                (MapChange::Remove(_), None) => None,
+                (MapChange::Update(diff), Some(current)) => {
+                    let mut diff = diff.clone();
+                    if let Err(mut e) = diff.rebase(current) {
+                        e.prefix_names(&format!("{:?}", key));
+                        error.merge(&e);
+                    }
+                    Some(MapChange::Update(diff))
+                }
+                (MapChange::Update(_), None) => {
+                    error.add_error(MismatchError::new_element(
+                        format!("{:?}", key),
+                        "a value",
+                        "no value",
+                        MismatchType::PatchOldValue,
+                    ));
+                    None
+                }
+                (MapChange::Insert(_), None) => Some(change.clone()),
+            };
+            changes.extend(change.map(|change| (key.clone(), change)));
+        }
+        if error.has_errors() {
+            return Err(error);
+        }
+        self.changes = changes;
+        Ok(())
+    }
+
+    /// Combines the changes of different keys. Both diffs may change the
+    /// same key only in the same way, updates are combined by the inner diff.
+    fn combine(&self, concurrent: &Self) -> Option<Self> {
+        let mut changes = self.changes.clone();
+        for (key, change) in &concurrent.changes {
+            let position = match changes.iter().position(|(changed, _)| changed == key) {
+                Some(position) => position,
+                None => {
+                    changes.push((key.clone(), change.clone()));
+                    continue;
+                }
+            };
+            match (&changes[position].1, change) {
+                (MapChange::Insert(value), MapChange::Insert(other))
+                | (MapChange::Remove(value), MapChange::Remove(other))
+                    if value.compare(other) => {}
+                (MapChange::Update(diff), MapChange::Update(other)) => {
+                    changes[position].1 = MapChange::Update(diff.combine(other)?);
+                }
+                _ => return None,
+            }
+        }
+        Some(MapDiff {
+            changes,
+            map: PhantomData,
+        })
+    }
+
+    /// Reports the first key that does not match, see `check` for all of
+    /// them.
+    fn applies_cleanly(&self, obj: &M) -> Result<(), MismatchError> {
+        self.check(obj)
+            .map_err(|e| e.mismatches().first().cloned().expect("an error"))
+    }
+
+    /// Checks every changed key.
+    ///
+    /// The name of a mismatch is the key, the patch puts the name of the
+    /// field in front of it, e.g. `scores."alice"`.
+    fn check(&self, obj: &M) -> Result<(), MultipleMismatchError> {
+        let mut error = MultipleMismatchError::new();
+        for (key, change) in &self.changes {
+            let name = format!("{:?}", key);
+            let current = obj.value(key);
+            let mismatch = |expected: String| {
+                MismatchError::new_element(
+                    name.clone(),
+                    expected,
+                    current.map_or_else(|| "no value".to_string(), |v| format!("{:?}", v)),
+                    MismatchType::PatchOldValue,
+                )
+            };
+            match (change, current) {
+                (MapChange::Insert(_), None) => {}
+                (MapChange::Insert(_), Some(_)) => {
+                    error.add_error(mismatch("no value".to_string()))
+                }
+                (MapChange::Remove(removed), Some(value)) if removed.compare(value) => {}
+                (MapChange::Remove(removed), _) => {
+                    error.add_error(mismatch(format!("{:?}", removed)))
+                }
+                (MapChange::Update(diff), Some(value)) => {
+                    if let Err(mut e) = diff.check(value) {
+                        e.prefix_names(&name);
+                        error.merge(&e);
+                    }
+                }
+                (MapChange::Update(_), None) => error.add_error(mismatch("a value".to_string())),
+            }
+        }
+        if error.has_errors() {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_into(&self, obj: &mut M) -> Result<(), MismatchError> {
+        self.applies_cleanly(obj)?;
+        for (key, change) in &self.changes {
+            match change {
+                MapChange::Insert(value) => obj.insert_value(key.clone(), value.clone()),
+                MapChange::Remove(_) => obj.remove_value(key),
+                MapChange::Update(diff) => {
+                    let value = obj.value_mut(key).expect("checked by applies_cleanly");
+                    diff.apply_into(value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K, V, D, M> Invert for MapDiff<K, V, D, M>
+where
+    K: Clone + Debug + PartialEq,
+    V: Clone + Debug + PartialEq + PatchableField,
+    D: Invert<Object = V> + Clone,
+    M: Map<K, V>,
+{
+    /// Inserted keys are removed and removed ones inserted again, updates
+    /// are inverted by the inner diff.
+    fn invert(&self) -> Self {
+        let changes = self
+            .changes
+            .iter()
+            .map(|(key, change)| {
+                let change = match change {
+                    MapChange::Insert(value) => MapChange::Remove(value.clone()),
+                    MapChange::Remove(value) => MapChange::Insert(value.clone()),
+                    MapChange::Update(diff) => MapChange::Update(diff.invert()),
+                };
+                (key.clone(), change)
+            })
+            .collect();
+        MapDiff {
+            changes,
+            map: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert::tests::*;
+
+    #[test]
+    fn test_sync() {
+        assert_sync::<MapDiff<String, u32>>();
+        assert_sync::<BTreeMapDiff<u8, String>>();
+    }
+
+    #[test]
+    fn test_send() {
+        assert_send::<MapDiff<String, u32>>();
+        assert_send::<BTreeMapDiff<u8, String>>();
+    }
+
+    fn map(entries: &[(u8, u32)]) -> BTreeMap<u8, u32> {
+        entries.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_merge() {
+        let cases = [
+            (map(&[]), map(&[(1, 1)]), map(&[(1, 2)])),
+            (map(&[(1, 1)]), map(&[]), map(&[(1, 2)])),
+            (map(&[(1, 1)]), map(&[]), map(&[(1, 1)])),
+            (map(&[]), map(&[(1, 1)]), map(&[])),
+            (map(&[(1, 1)]), map(&[(1, 2)]), map(&[(1, 3)])),
+            (
+                map(&[(1, 1), (2, 2)]),
+                map(&[(2, 3)]),
+                map(&[(2, 3), (3, 3)]),
+            ),
+        ];
+        for (a, b, c) in &cases {
+            let mut diff: BTreeMapDiff<u8, u32> = Diff::new(a, b);
+
+            assert_eq!(diff.merge(&Diff::new(b, c)), Ok(()));
+            let mut obj = a.clone();
+            assert_eq!(diff.apply_into(&mut obj), Ok(()));
+            assert_eq!(obj, *c, "{:?} -> {:?} -> {:?}", a, b, c);
+        }
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let mut diff: BTreeMapDiff<u8, u32> = Diff::new(&map(&[]), &map(&[(1, 1)]));
+
+        assert_eq!(diff.merge(&Diff::new(&map(&[]), &map(&[(1, 2)]))), Err(()));
+        assert_eq!(diff, Diff::new(&map(&[]), &map(&[(1, 1)])));
+    }
+}
@@ -7,9 +7,19 @@
 //! This module contains the trait for `Diff` and some generic implementations.
 
 mod copy;
+mod map;
+mod nested;
 mod numeric_distance;
+mod set;
+mod text;
 mod traits;
+mod vec;
 
 pub use copy::CopyDiff;
+pub use map::{BTreeMapDiff, Map, MapChange, MapDiff};
+pub use nested::NestedPatchDiff;
 pub use numeric_distance::NumericDistanceDiff;
-pub use traits::Diff;
+pub use set::{BTreeSetDiff, Lenient, Set, SetDiff, SetMode, Strict};
+pub use text::{Chars, Granularity, Lines, TextDiff, TextHunk, Words};
+pub use traits::{Diff, Invert, Priority, Transform};
+pub use vec::{VecDiff, VecEdit};
@@ -0,0 +1,152 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use crate::{
+    diff::{Diff, Invert},
+    mismatch::{MismatchError, MultipleMismatchError},
+    traits::{Base, InvertPatch, Patch, PatchableExt},
+};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Stores the changes of a field whose type derives `Patch` as a patch of that
+/// type, instead of a copy of the whole value.
+///
+/// Applying it only changes the fields of the nested struct that are part of
+/// the patch. Mismatches are reported with the path to the nested field, e.g.
+/// `address.city`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T::Patch: Serialize",
+        deserialize = "T::Patch: Deserialize<'de>"
+    ))
+)]
+pub struct NestedPatchDiff<T>
+where
+    T: PatchableExt,
+{
+    patch: T::Patch,
+}
+
+impl<T> NestedPatchDiff<T>
+where
+    T: PatchableExt,
+{
+    /// Wraps an existing patch of the nested struct.
+    pub fn from_patch(patch: T::Patch) -> NestedPatchDiff<T> {
+        NestedPatchDiff { patch }
+    }
+
+    /// The patch of the nested struct.
+    pub fn patch(&self) -> &T::Patch {
+        &self.patch
+    }
+}
+
+impl<T> Diff for NestedPatchDiff<T>
+where
+    T: PatchableExt,
+    T::Patch: Clone,
+{
+    type DiffResult = T::Patch;
+    type Object = T;
+
+    fn new(old: &T, new: &T) -> Self {
+        NestedPatchDiff {
+            patch: old.get_patch(new),
+        }
+    }
+
+    fn changes_object(&self, _old: &T) -> bool {
+        self.contains_change()
+    }
+
+    fn contains_change(&self) -> bool {
+        !self.patch.is_empty()
+    }
+
+    /// Merges the nested patches, see `Patch::merge_into`.
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        rhs.patch.merge_into(&mut self.patch).map_err(|_| ())
+    }
+
+    /// Reports the first mismatch of the nested patch, see `check` for all of
+    /// them.
+    fn applies_cleanly(&self, obj: &T) -> Result<(), MismatchError> {
+        self.check(obj)
+            .map_err(|e| e.mismatches().first().cloned().expect("an error"))
+    }
+
+    fn check(&self, obj: &T) -> Result<(), MultipleMismatchError> {
+        Patch::check(&self.patch, obj)
+    }
+
+    /// Rebases the nested patch, see `Patch::rebase`.
+    fn rebase(&mut self, obj: &T) -> Result<(), MultipleMismatchError> {
+        self.patch.rebase(obj)
+    }
+
+    fn cleanup(&mut self) -> bool {
+        self.patch.cleanup()
+    }
+
+    fn apply_into(&self, obj: &mut T) -> Result<(), MismatchError> {
+        self.patch
+            .apply(obj)
+            .map_err(|e| e.mismatches().first().cloned().expect("an error"))
+    }
+}
+
+impl<T> Invert for NestedPatchDiff<T>
+where
+    T: PatchableExt,
+    T::Patch: Clone + InvertPatch,
+{
+    /// Inverts the nested patch, see `InvertPatch::invert`.
+    fn invert(&self) -> Self {
+        NestedPatchDiff {
+            patch: self.patch.invert(),
+        }
+    }
+}
+
+impl<T> Clone for NestedPatchDiff<T>
+where
+    T: PatchableExt,
+    T::Patch: Clone,
+{
+    fn clone(&self) -> Self {
+        NestedPatchDiff {
+            patch: self.patch.clone(),
+        }
+    }
+}
+
+impl<T> PartialEq for NestedPatchDiff<T>
+where
+    T: PatchableExt,
+    T::Patch: PartialEq,
+{
+    fn eq(&self, rhs: &Self) -> bool {
+        self.patch == rhs.patch
+    }
+}
+
+impl<T> fmt::Debug for NestedPatchDiff<T>
+where
+    T: PatchableExt,
+    T::Patch: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NestedPatchDiff")
+            .field("patch", &self.patch)
+            .finish()
+    }
+}
@@ -4,7 +4,10 @@
  * For more information, see the LICENSE.md file in this repository.
  */
 
-use crate::{diff::Diff, mismatch::MismatchError};
+use crate::{
+    diff::{Diff, Invert, Priority, Transform},
+    mismatch::MismatchError,
+};
 use num::Zero;
 
 #[cfg(feature = "serde")]
@@ -34,8 +37,14 @@ where
 impl<T> Diff for NumericDistanceDiff<T>
 where
     T: std::ops::Sub<T> + Copy + std::ops::Add<<T as std::ops::Sub>::Output, Output = T>,
-    <T as std::ops::Sub>::Output:
-        num::Zero + Clone + Copy + std::fmt::Debug + PartialEq + Serialize + DeserializeOwned,
+    <T as std::ops::Sub>::Output: num::Zero
+        + std::ops::Sub<Output = <T as std::ops::Sub>::Output>
+        + Clone
+        + Copy
+        + std::fmt::Debug
+        + PartialEq
+        + Serialize
+        + DeserializeOwned,
 {
     type DiffResult = <T as std::ops::Sub>::Output;
     type Object = T;
@@ -75,6 +84,48 @@ where
         self.difference = self.difference + rhs.difference;
         Ok(())
     }
+
+    /// Adds up both differences, concurrent changes of a number never
+    /// conflict.
+    fn combine(&self, concurrent: &Self) -> Option<Self> {
+        Some(NumericDistanceDiff {
+            difference: self.difference + concurrent.difference,
+        })
+    }
+}
+
+/// Only for signed differences, the difference of an unsigned type can not
+/// be negated.
+impl<T> Invert for NumericDistanceDiff<T>
+where
+    T: std::ops::Sub<T> + Copy + std::ops::Add<<T as std::ops::Sub>::Output, Output = T>,
+    <T as std::ops::Sub>::Output:
+        num::Signed + Clone + Copy + std::fmt::Debug + PartialEq + Serialize + DeserializeOwned,
+{
+    /// Negates the difference.
+    fn invert(&self) -> Self {
+        NumericDistanceDiff {
+            difference: -self.difference,
+        }
+    }
+}
+
+impl<T> Transform for NumericDistanceDiff<T>
+where
+    T: std::ops::Sub<T> + Copy + std::ops::Add<<T as std::ops::Sub>::Output, Output = T>,
+    <T as std::ops::Sub>::Output: num::Zero
+        + std::ops::Sub<Output = <T as std::ops::Sub>::Output>
+        + Clone
+        + Copy
+        + std::fmt::Debug
+        + PartialEq
+        + Serialize
+        + DeserializeOwned,
+{
+    /// Additions commute, the diff stays the same.
+    fn transform(&self, _concurrent: &Self, _priority: Priority) -> Self {
+        *self
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,392 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use crate::{
+    diff::{Diff, Invert},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The operations of a set a `SetDiff` needs.
+pub trait Set<T> {
+    /// True if `element` is part of the set.
+    fn contains_element(&self, element: &T) -> bool;
+
+    /// Adds `element` to the set.
+    fn insert_element(&mut self, element: T);
+
+    /// Removes `element` from the set.
+    fn remove_element(&mut self, element: &T);
+
+    /// All elements of the set.
+    fn elements<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a>;
+}
+
+impl<T> Set<T> for HashSet<T>
+where
+    T: Eq + Hash,
+{
+    fn contains_element(&self, element: &T) -> bool {
+        self.contains(element)
+    }
+
+    fn insert_element(&mut self, element: T) {
+        self.insert(element);
+    }
+
+    fn remove_element(&mut self, element: &T) {
+        self.remove(element);
+    }
+
+    fn elements<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a> {
+        Box::new(self.iter())
+    }
+}
+
+impl<T> Set<T> for BTreeSet<T>
+where
+    T: Ord,
+{
+    fn contains_element(&self, element: &T) -> bool {
+        self.contains(element)
+    }
+
+    fn insert_element(&mut self, element: T) {
+        self.insert(element);
+    }
+
+    fn remove_element(&mut self, element: &T) {
+        self.remove(element);
+    }
+
+    fn elements<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a> {
+        Box::new(self.iter())
+    }
+}
+
+/// Whether a `SetDiff` conflicts with a set that already contains the
+/// elements it adds or lacks the ones it removes.
+pub trait SetMode {
+    /// True if these elements are reported as mismatches.
+    const STRICT: bool;
+}
+
+/// Adding a present or removing an absent element is a mismatch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Strict;
+
+impl SetMode for Strict {
+    const STRICT: bool = true;
+}
+
+/// A `SetDiff` never conflicts, adding a present or removing an absent
+/// element does nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Lenient;
+
+impl SetMode for Lenient {
+    const STRICT: bool = false;
+}
+
+/// Stores the elements added to and removed from a set, instead of two
+/// copies of the whole set.
+///
+/// Only the added and removed elements are checked when the diff is applied.
+/// `C` is the `SetMode`, by default `Strict`: adding an element that is
+/// present already or removing one that is absent is a mismatch. `Lenient`
+/// diffs never conflict.
+///
+/// `S` is the set, `HashSet<T>` by default, see `BTreeSetDiff` for a
+/// `BTreeSet`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))
+)]
+pub struct SetDiff<T, S = HashSet<T>, C = Strict>
+where
+    C: SetMode,
+{
+    added: Vec<T>,
+    removed: Vec<T>,
+    set: PhantomData<S>,
+    mode: PhantomData<C>,
+}
+
+/// A `SetDiff` of a `BTreeSet`.
+pub type BTreeSetDiff<T, C = Strict> = SetDiff<T, BTreeSet<T>, C>;
+
+impl<T, S, C> SetDiff<T, S, C>
+where
+    C: SetMode,
+{
+    /// The elements that are added to the set.
+    pub fn added(&self) -> &[T] {
+        &self.added
+    }
+
+    /// The elements that are removed from the set.
+    pub fn removed(&self) -> &[T] {
+        &self.removed
+    }
+}
+
+/// True if `lhs` and `rhs` contain the same elements, in any order.
+fn same_elements<T: PartialEq>(lhs: &[T], rhs: &[T]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().all(|element| rhs.contains(element))
+}
+
+impl<T, S, C> PartialEq for SetDiff<T, S, C>
+where
+    T: PartialEq,
+    C: SetMode,
+{
+    /// The order of the elements does not matter.
+    fn eq(&self, rhs: &Self) -> bool {
+        same_elements(&self.added, &rhs.added) && same_elements(&self.removed, &rhs.removed)
+    }
+}
+
+impl<T, S, C> Diff for SetDiff<T, S, C>
+where
+    T: Clone + Debug + PartialEq,
+    S: Set<T>,
+    C: SetMode + Clone,
+{
+    type DiffResult = (Vec<T>, Vec<T>);
+    type Object = S;
+
+    fn new(old: &S, new: &S) -> Self {
+        SetDiff {
+            added: new
+                .elements()
+                .filter(|element| !old.contains_element(element))
+                .cloned()
+                .collect(),
+            removed: old
+                .elements()
+                .filter(|element| !new.contains_element(element))
+                .cloned()
+                .collect(),
+            set: PhantomData,
+            mode: PhantomData,
+        }
+    }
+
+    fn changes_object(&self, old: &S) -> bool {
+        self.added
+            .iter()
+            .any(|element| !old.contains_element(element))
+            || self
+                .removed
+                .iter()
+                .any(|element| old.contains_element(element))
+    }
+
+    fn contains_change(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty()
+    }
+
+    /// Combines the elements of both diffs, the order of the diffs does not
+    /// matter.
+    ///
+    /// An element that one diff adds and the other one removes is dropped. A
+    /// `Strict` diff fails if both add or both remove the same element.
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        let mut added = self.added.clone();
+        let mut removed = self.removed.clone();
+        for element in &rhs.added {
+            if let Some(position) = removed.iter().position(|e| e == element) {
+                removed.remove(position);
+            } else if !added.contains(element) {
+                added.push(element.clone());
+            } else if C::STRICT {
+                return Err(());
+            }
+        }
+        for element in &rhs.removed {
+            if let Some(position) = added.iter().position(|e| e == element) {
+                added.remove(position);
+            } else if !removed.contains(element) {
+                removed.push(element.clone());
+            } else if C::STRICT {
+                return Err(());
+            }
+        }
+        self.added = added;
+        self.removed = removed;
+        Ok(())
+    }
+
+    /// Drops the added elements `obj` contains already and the removed ones
+    /// it lacks, never fails.
+    fn rebase(&mut self, obj: &S) -> Result<(), MultipleMismatchError> {
+        self.added.retain(|element| !obj.contains_element(element));
+        self.removed.retain(|element| obj.contains_element(element));
+        Ok(())
+    }
+
+    /// Adds and removes the elements of both diffs, each element only once.
+    ///
+    /// Fails if one diff adds an element the other one removes.
+    fn combine(&self, concurrent: &Self) -> Option<Self> {
+        if self.added.iter().any(|e| concurrent.removed.contains(e))
+            || self.removed.iter().any(|e| concurrent.added.contains(e))
+        {
+            return None;
+        }
+        let mut added = self.added.clone();
+        let mut removed = self.removed.clone();
+        for element in &concurrent.added {
+            if !added.contains(element) {
+                added.push(element.clone());
+            }
+        }
+        for element in &concurrent.removed {
+            if !removed.contains(element) {
+                removed.push(element.clone());
+            }
+        }
+        Some(SetDiff {
+            added,
+            removed,
+            set: PhantomData,
+            mode: PhantomData,
+        })
+    }
+
+    /// Reports the first element that does not match, see `check` for all
+    /// of them.
+    fn applies_cleanly(&self, obj: &S) -> Result<(), MismatchError> {
+        self.check(obj)
+            .map_err(|e| e.mismatches().first().cloned().expect("an error"))
+    }
+
+    /// Checks the added and removed elements of a `Strict` diff.
+    ///
+    /// The name of a mismatch is the element, the patch puts the name of the
+    /// field in front of it, e.g. `tags."new"`.
+    fn check(&self, obj: &S) -> Result<(), MultipleMismatchError> {
+        let mut error = MultipleMismatchError::new();
+        if C::STRICT {
+            let mismatch = |element: &T, expected: &str, received: &str| {
+                MismatchError::new_element(
+                    format!("{:?}", element),
+                    expected,
+                    received,
+                    MismatchType::PatchOldValue,
+                )
+            };
+            for element in self.added.iter().filter(|e| obj.contains_element(e)) {
+                error.add_error(mismatch(element, "absent", "present"));
+            }
+            for element in self.removed.iter().filter(|e| !obj.contains_element(e)) {
+                error.add_error(mismatch(element, "present", "absent"));
+            }
+        }
+        if error.has_errors() {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_into(&self, obj: &mut S) -> Result<(), MismatchError> {
+        self.applies_cleanly(obj)?;
+        for element in &self.removed {
+            obj.remove_element(element);
+        }
+        for element in &self.added {
+            obj.insert_element(element.clone());
+        }
+        Ok(())
+    }
+}
+
+impl<T, S, C> Invert for SetDiff<T, S, C>
+where
+    T: Clone + Debug + PartialEq,
+    S: Set<T>,
+    C: SetMode + Clone,
+{
+    /// Swaps the added and the removed elements.
+    fn invert(&self) -> Self {
+        SetDiff {
+            added: self.removed.clone(),
+            removed: self.added.clone(),
+            set: PhantomData,
+            mode: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert::tests::*;
+
+    #[test]
+    fn test_sync() {
+        assert_sync::<SetDiff<String>>();
+        assert_sync::<BTreeSetDiff<u8, Lenient>>();
+    }
+
+    #[test]
+    fn test_send() {
+        assert_send::<SetDiff<String>>();
+        assert_send::<BTreeSetDiff<u8, Lenient>>();
+    }
+
+    fn set(elements: &[u8]) -> BTreeSet<u8> {
+        elements.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_merge_is_commutative() {
+        let base = set(&[1, 2, 3]);
+        let first: BTreeSetDiff<u8> = Diff::new(&base, &set(&[1, 2, 4]));
+        let second: BTreeSetDiff<u8> = Diff::new(&base, &set(&[2, 3, 5]));
+
+        let mut lhs = first.clone();
+        let mut rhs = second.clone();
+        assert_eq!(lhs.merge(&second), Ok(()));
+        assert_eq!(rhs.merge(&first), Ok(()));
+
+        assert_eq!(lhs, rhs);
+        let mut obj = base;
+        assert_eq!(lhs.apply_into(&mut obj), Ok(()));
+        assert_eq!(obj, set(&[2, 4, 5]));
+    }
+
+    #[test]
+    fn test_merge_cancels() {
+        let mut diff: BTreeSetDiff<u8> = Diff::new(&set(&[1]), &set(&[]));
+
+        assert_eq!(diff.merge(&Diff::new(&set(&[]), &set(&[1]))), Ok(()));
+        assert!(!diff.contains_change());
+    }
+
+    #[test]
+    fn test_merge_strict_conflict() {
+        let mut strict: BTreeSetDiff<u8> = Diff::new(&set(&[]), &set(&[1]));
+        assert_eq!(strict.merge(&strict.clone()), Err(()));
+
+        let mut lenient: BTreeSetDiff<u8, Lenient> = Diff::new(&set(&[]), &set(&[1]));
+        assert_eq!(lenient.merge(&lenient.clone()), Ok(()));
+        assert_eq!(lenient.added(), [1]);
+    }
+}
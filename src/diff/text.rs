@@ -0,0 +1,496 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use crate::{
+    diff::{Diff, Invert, Priority, Transform, VecDiff, VecEdit},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
+};
+use std::{cmp, collections::BTreeMap, convert::TryFrom, iter, marker::PhantomData};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The number of unchanged tokens stored in front of and after every hunk.
+const CONTEXT: usize = 3;
+
+/// How a `TextDiff` splits a text into the tokens it compares.
+pub trait Granularity {
+    /// Splits `text` into tokens. Joined together, they are `text` again.
+    fn tokens(text: &str) -> Vec<&str>;
+}
+
+/// Compares texts char by char.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Chars;
+
+impl Granularity for Chars {
+    fn tokens(text: &str) -> Vec<&str> {
+        text.char_indices()
+            .map(|(start, c)| &text[start..start + c.len_utf8()])
+            .collect()
+    }
+}
+
+/// Compares texts word by word, the whitespace between words is a token of
+/// its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Words;
+
+impl Granularity for Words {
+    fn tokens(text: &str) -> Vec<&str> {
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((_, c)) = chars.next() {
+            if let Some(&(next, next_c)) = chars.peek() {
+                if c.is_whitespace() != next_c.is_whitespace() {
+                    tokens.push(&text[start..next]);
+                    start = next;
+                }
+            }
+        }
+        if start < text.len() {
+            tokens.push(&text[start..]);
+        }
+        tokens
+    }
+}
+
+/// Compares texts line by line, each line keeps its line break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Lines;
+
+impl Granularity for Lines {
+    fn tokens(text: &str) -> Vec<&str> {
+        text.split_inclusive('\n').collect()
+    }
+}
+
+/// Stores the changes of a `String` as hunks, like `diff -u` does, instead of
+/// two copies of the whole text.
+///
+/// `G` is the `Granularity` the text is compared at, e.g.
+/// `#[patch(diff = TextDiff<Words>)]`. It defaults to `Lines`.
+///
+/// Every hunk stores a few unchanged tokens around it as context. Only the
+/// hunks and their context have to match when the diff is applied, so
+/// unrelated changes elsewhere in the text do not conflict. A hunk that is
+/// moved by these changes is searched for like `patch(1)` does, starting at
+/// the position it is expected at, see `apply_fuzzy` to limit the search.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextDiff<G = Lines>
+where
+    G: Granularity,
+{
+    hunks: Vec<TextHunk>,
+    granularity: PhantomData<G>,
+}
+
+/// A changed part of the text: the tokens `removed`, starting at token
+/// `index` of the original text, are replaced by `inserted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TextHunk {
+    index: usize,
+    before: Vec<String>,
+    removed: Vec<String>,
+    inserted: Vec<String>,
+    after: Vec<String>,
+}
+
+impl TextHunk {
+    /// The position of the first removed token in the original text.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The unchanged tokens in front of the hunk.
+    pub fn before(&self) -> &[String] {
+        &self.before
+    }
+
+    /// The tokens that are replaced.
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// The tokens that are put in place of the `removed` ones.
+    pub fn inserted(&self) -> &[String] {
+        &self.inserted
+    }
+
+    /// The unchanged tokens after the hunk.
+    pub fn after(&self) -> &[String] {
+        &self.after
+    }
+
+    /// True if the hunk and its context are found in `tokens` at `position`,
+    /// the position of the first removed token.
+    fn matches_at(&self, tokens: &[&str], position: isize) -> bool {
+        let start = position - self.before.len() as isize;
+        let expected = self.before.iter().chain(&self.removed).chain(&self.after);
+        let len = self.before.len() + self.removed.len() + self.after.len();
+        start >= 0
+            && start as usize + len <= tokens.len()
+            && tokens[start as usize..]
+                .iter()
+                .zip(expected)
+                .all(|(token, expected)| token == expected)
+    }
+
+    /// The error if the hunk is not found at `position`.
+    fn mismatch(&self, tokens: &[&str], position: usize) -> MismatchError {
+        let start = position.saturating_sub(self.before.len());
+        let len = self.before.len() + self.removed.len() + self.after.len();
+        let expected: String = self
+            .before
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.after)
+            .map(String::as_str)
+            .collect();
+        let received: String = tokens.iter().skip(start).take(len).copied().collect();
+        MismatchError::new_element(
+            self.index.to_string(),
+            format!("{:?}", expected),
+            format!("{:?}", received),
+            MismatchType::PatchOldValue,
+        )
+    }
+}
+
+impl<G> TextDiff<G>
+where
+    G: Granularity,
+{
+    /// The hunks of this diff, sorted by their index.
+    pub fn hunks(&self) -> &[TextHunk] {
+        &self.hunks
+    }
+
+    /// Like `applies_cleanly`, but a hunk is only found up to `max_offset`
+    /// tokens away from where it is expected, e.g. because of tokens that
+    /// were inserted in front of it. `applies_cleanly` searches the whole
+    /// text.
+    ///
+    /// Once a hunk is found at an offset, the following hunks are expected
+    /// at the same offset.
+    pub fn applies_fuzzy(&self, obj: &str, max_offset: usize) -> Result<(), MismatchError> {
+        let tokens = G::tokens(obj);
+        match self
+            .locate(&tokens, max_offset)
+            .into_iter()
+            .find_map(Result::err)
+        {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `apply_into`, but with the limit of `applies_fuzzy`.
+    pub fn apply_fuzzy(&self, obj: &mut String, max_offset: usize) -> Result<(), MismatchError> {
+        let tokens = G::tokens(obj);
+        let positions = self
+            .locate(&tokens, max_offset)
+            .into_iter()
+            .collect::<Result<Vec<usize>, MismatchError>>()?;
+
+        let mut result = String::with_capacity(obj.len());
+        let mut consumed = 0;
+        for (hunk, position) in self.hunks.iter().zip(positions) {
+            result.extend(tokens[consumed..position].iter().copied());
+            result.extend(hunk.inserted.iter().map(String::as_str));
+            consumed = position + hunk.removed.len();
+        }
+        result.extend(tokens[consumed..].iter().copied());
+        *obj = result;
+        Ok(())
+    }
+
+    /// Searches every hunk in `tokens`, returns the position of its first
+    /// removed token.
+    fn locate(&self, tokens: &[&str], max_offset: usize) -> Vec<Result<usize, MismatchError>> {
+        let mut result = Vec::new();
+        let mut drift = 0isize;
+        // hunks must not overlap the removed tokens of the previous one
+        let mut min_position = 0isize;
+        for hunk in &self.hunks {
+            let expected = hunk.index as isize + drift;
+            // further away, no position is within the text
+            let reach = cmp::max(expected - min_position, tokens.len() as isize - expected);
+            let max_offset = cmp::min(isize::try_from(max_offset).unwrap_or(isize::MAX), reach);
+            let found = (0..=max_offset)
+                .flat_map(|offset| {
+                    iter::once(expected - offset)
+                        .chain(Some(expected + offset).filter(|_| offset > 0))
+                })
+                .filter(|&position| position >= min_position)
+                .find(|&position| hunk.matches_at(tokens, position));
+            match found {
+                Some(position) => {
+                    drift = position - hunk.index as isize;
+                    min_position = position + hunk.removed.len() as isize;
+                    result.push(Ok(position as usize));
+                }
+                None => result.push(Err(hunk.mismatch(tokens, cmp::max(expected, 0) as usize))),
+            }
+        }
+        result
+    }
+
+    /// The hunks as an edit script of the tokens.
+    fn edits(&self) -> VecDiff<String> {
+        VecDiff::from_edits(
+            self.hunks
+                .iter()
+                .map(|hunk| {
+                    VecEdit::replace(hunk.index, hunk.removed.clone(), hunk.inserted.clone())
+                })
+                .collect(),
+        )
+    }
+
+    /// Turns `edits` into hunks, taking the context out of the `known`
+    /// tokens of the original text.
+    fn from_edits(edits: &[VecEdit<String>], known: &BTreeMap<usize, &String>) -> TextDiff<G> {
+        let mut hunks = Vec::new();
+        for (i, edit) in edits.iter().enumerate() {
+            let start = edit.index();
+            let end = start + edit.removed().len();
+            // the context never reaches into the neighbouring edits
+            let previous_end = match i {
+                0 => 0,
+                _ => edits[i - 1].index() + edits[i - 1].removed().len(),
+            };
+            let next_start = edits.get(i + 1).map_or(usize::MAX, VecEdit::index);
+
+            let mut before: Vec<String> = (previous_end..start)
+                .rev()
+                .take(CONTEXT)
+                .map_while(|index| known.get(&index).map(|token| token.to_string()))
+                .collect();
+            before.reverse();
+            let after = (end..next_start)
+                .take(CONTEXT)
+                .map_while(|index| known.get(&index).map(|token| token.to_string()))
+                .collect();
+
+            hunks.push(TextHunk {
+                index: start,
+                before,
+                removed: edit.removed().to_vec(),
+                inserted: edit.inserted().to_vec(),
+                after,
+            });
+        }
+        TextDiff {
+            hunks,
+            granularity: PhantomData,
+        }
+    }
+
+    /// The tokens of the original text that are stored in this diff, by
+    /// their index.
+    fn known_tokens(&self) -> BTreeMap<usize, &String> {
+        let mut known = BTreeMap::new();
+        for hunk in &self.hunks {
+            let start = hunk.index - hunk.before.len();
+            let tokens = hunk.before.iter().chain(&hunk.removed).chain(&hunk.after);
+            known.extend((start..).zip(tokens));
+        }
+        known
+    }
+
+    /// The index in the original text of the token at `index` of the changed
+    /// text, `None` if this diff inserted the token.
+    fn original_index(&self, index: usize) -> Option<usize> {
+        let mut shift = 0isize;
+        for hunk in &self.hunks {
+            let start = (hunk.index as isize + shift) as usize;
+            if index < start {
+                break;
+            }
+            if index < start + hunk.inserted.len() {
+                return None;
+            }
+            shift += hunk.inserted.len() as isize - hunk.removed.len() as isize;
+        }
+        Some((index as isize - shift) as usize)
+    }
+}
+
+impl<G> Diff for TextDiff<G>
+where
+    G: Granularity + Clone,
+{
+    type DiffResult = Vec<TextHunk>;
+    type Object = String;
+
+    fn new(old: &String, new: &String) -> Self {
+        let old_tokens: Vec<String> = G::tokens(old).into_iter().map(str::to_string).collect();
+        let new_tokens = G::tokens(new).into_iter().map(str::to_string).collect();
+        let edits: VecDiff<String> = Diff::new(&old_tokens, &new_tokens);
+        TextDiff::from_edits(edits.edits(), &old_tokens.iter().enumerate().collect())
+    }
+
+    fn changes_object(&self, old: &String) -> bool {
+        self.contains_change() && self.applies_cleanly(old).is_ok()
+    }
+
+    fn contains_change(&self) -> bool {
+        !self.hunks.is_empty()
+    }
+
+    /// Combines the hunks of both diffs.
+    ///
+    /// Fails if `rhs` expects other tokens than the ones this diff puts into
+    /// the text.
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        let mut edits = self.edits();
+        edits.merge(&rhs.edits())?;
+
+        let mut known = self.known_tokens();
+        for (index, token) in rhs.known_tokens() {
+            if let Some(index) = self.original_index(index) {
+                known.entry(index).or_insert(token);
+            }
+        }
+        *self = TextDiff::from_edits(edits.edits(), &known);
+        Ok(())
+    }
+
+    /// Searches every hunk by its context, see `applies_fuzzy`.
+    fn applies_cleanly(&self, obj: &String) -> Result<(), MismatchError> {
+        self.applies_fuzzy(obj, usize::MAX)
+    }
+
+    /// Reports every hunk that is not found.
+    fn check(&self, obj: &String) -> Result<(), MultipleMismatchError> {
+        let mut error = MultipleMismatchError::new();
+        for mismatch in self
+            .locate(&G::tokens(obj), usize::MAX)
+            .into_iter()
+            .filter_map(Result::err)
+        {
+            error.add_error(mismatch);
+        }
+        if error.has_errors() {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_into(&self, obj: &mut String) -> Result<(), MismatchError> {
+        self.apply_fuzzy(obj, usize::MAX)
+    }
+}
+
+impl<G> Invert for TextDiff<G>
+where
+    G: Granularity + Clone,
+{
+    /// Swaps the removed and inserted tokens of every hunk.
+    ///
+    /// The context is not changed by the diff, so it stays the same. The
+    /// indices are moved to the changed text.
+    fn invert(&self) -> Self {
+        let mut shift = 0isize;
+        let hunks = self
+            .hunks
+            .iter()
+            .map(|hunk| {
+                let inverted = TextHunk {
+                    index: (hunk.index as isize + shift) as usize,
+                    before: hunk.before.clone(),
+                    removed: hunk.inserted.clone(),
+                    inserted: hunk.removed.clone(),
+                    after: hunk.after.clone(),
+                };
+                shift += hunk.inserted.len() as isize - hunk.removed.len() as isize;
+                inverted
+            })
+            .collect();
+        TextDiff {
+            hunks,
+            granularity: PhantomData,
+        }
+    }
+}
+
+impl<G> Transform for TextDiff<G>
+where
+    G: Granularity + Clone,
+{
+    /// Transforms the hunks like a `VecDiff` of the tokens, see
+    /// `VecDiff::transform`. The context is taken from the text after
+    /// `concurrent`.
+    ///
+    /// The tokens of both diffs have to stay the same when the text is split
+    /// again, e.g. `Words` inserted next to each other may become one.
+    fn transform(&self, concurrent: &Self, priority: Priority) -> Self {
+        let edits = self.edits().transform(&concurrent.edits(), priority);
+
+        // the inverted diff knows the tokens of the text after `concurrent`
+        let inverted = concurrent.invert();
+        let mut known = inverted.known_tokens();
+        for (index, token) in self.known_tokens() {
+            if let Some(index) = inverted.original_index(index) {
+                known.entry(index).or_insert(token);
+            }
+        }
+        TextDiff::from_edits(edits.edits(), &known)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert::tests::*;
+
+    #[test]
+    fn test_sync() {
+        assert_sync::<TextDiff<Chars>>();
+        assert_sync::<TextDiff<Words>>();
+        assert_sync::<TextDiff<Lines>>();
+    }
+
+    #[test]
+    fn test_send() {
+        assert_send::<TextDiff<Chars>>();
+        assert_send::<TextDiff<Words>>();
+        assert_send::<TextDiff<Lines>>();
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(Chars::tokens("aä b"), ["a", "ä", " ", "b"]);
+        assert_eq!(
+            Words::tokens(" one  two\nthree"),
+            [" ", "one", "  ", "two", "\n", "three"]
+        );
+        assert_eq!(
+            Lines::tokens("one\ntwo\n\nthree"),
+            ["one\n", "two\n", "\n", "three"]
+        );
+        assert!(Words::tokens("").is_empty());
+    }
+
+    #[test]
+    fn test_context_stops_at_neighbours() {
+        let diff: TextDiff<Chars> = Diff::new(&"abcdefgh".to_string(), &"aXcdeYgh".to_string());
+
+        assert_eq!(diff.hunks().len(), 2);
+        assert_eq!(diff.hunks()[0].before(), ["a"]);
+        assert_eq!(diff.hunks()[0].after(), ["c", "d", "e"]);
+        assert_eq!(diff.hunks()[1].before(), ["c", "d", "e"]);
+        assert_eq!(diff.hunks()[1].after(), ["g", "h"]);
+    }
+}
@@ -4,7 +4,7 @@
  * For more information, see the LICENSE.md file in this repository.
  */
 
-use crate::mismatch::MismatchError;
+use crate::mismatch::{MismatchError, MultipleMismatchError};
 
 /// Trait to specify different Diffing Techniques.Eq
 /// The idea is, that we don't always have to store are copy of the new value.
@@ -52,11 +52,52 @@ where
     /// Applies the given `Diff` ontop of this on.
     /// That means that `a.merge(b)` changes`a` so that it represents a change
     /// as if `a` and `b` have been applied consecutevly.Eq
+    #[allow(clippy::result_unit_err)]
     fn merge(&mut self, rhs: &Self) -> Result<(), ()>;
 
+    /// Combines this diff with `concurrent`, a diff made of the same old
+    /// value, into one that makes both changes, see `Patch::merge_concurrent`.
+    ///
+    /// Returns `None` if the changes can not be combined. The default
+    /// implementation never combines.
+    fn combine(&self, _concurrent: &Self) -> Option<Self> {
+        None
+    }
+
     /// checks the obj if this diff applies without a merge conflict.
     fn applies_cleanly(&self, obj: &Self::Object) -> Result<(), MismatchError>;
 
+    /// Like `applies_cleanly`, but reports all mismatches instead of only one.
+    ///
+    /// Diffs that consist of several fields, e.g. a `NestedPatchDiff`, report
+    /// a mismatch per field. The default implementation forwards to
+    /// `applies_cleanly`.
+    fn check(&self, obj: &Self::Object) -> Result<(), MultipleMismatchError> {
+        self.applies_cleanly(obj).map_err(|e| {
+            let mut error = MultipleMismatchError::new();
+            error.add_error(e);
+            error
+        })
+    }
+
+    /// Changes the old values this diff expects to the ones of `obj`, but
+    /// keeps the values it sets, see `Patch::rebase`.
+    ///
+    /// Diffs that do not store the values they set can not be rebased. The
+    /// default implementation keeps the diff and fails like `check` if it does
+    /// not apply to `obj`. The diff is unchanged if an error is returned.
+    fn rebase(&mut self, obj: &Self::Object) -> Result<(), MultipleMismatchError> {
+        self.check(obj)
+    }
+
+    /// removes useless parts of this diff, see `Patch::cleanup`.
+    ///
+    /// Returns true if something was removed. The default implementation
+    /// does nothing.
+    fn cleanup(&mut self) -> bool {
+        false
+    }
+
     /// Applies this `diff` onto the given `object``
     ///
     /// # Arguments
@@ -65,3 +106,37 @@ where
     /// Should fail, if applies_cleany returns an error!
     fn apply_into(&self, obj: &mut Self::Object) -> Result<(), MismatchError>;
 }
+
+/// Diffs that can be undone, e.g. by `history::UndoStack`.
+///
+/// Diffs that do not store enough to restore the old value, e.g. one that
+/// only stores the new value, do not implement it.
+pub trait Invert: Diff {
+    /// The diff that undoes this one.
+    ///
+    /// Applying the inverted diff to an object this diff was applied to
+    /// restores the object's old value.
+    fn invert(&self) -> Self;
+}
+
+/// Which of two concurrent diffs wins where both change the same part of a
+/// value, see `Transform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// The diff that is transformed wins.
+    Ours,
+    /// The concurrent diff wins.
+    Theirs,
+}
+
+/// Operational transformation of diffs, e.g. for collaborative editing.
+///
+/// Two diffs `a` and `b` made concurrently of the same value converge: `a`
+/// followed by `b.transform(&a, Priority::Theirs)` changes the value in the
+/// same way as `b` followed by `a.transform(&b, Priority::Ours)`. Both calls
+/// have to agree on the diff that wins.
+pub trait Transform: Diff {
+    /// Transforms this diff, so it applies after `concurrent`, which was made
+    /// of the same value.
+    fn transform(&self, concurrent: &Self, priority: Priority) -> Self;
+}
@@ -0,0 +1,648 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use crate::{
+    diff::{Diff, Invert, Priority, Transform},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
+    patchable::PatchableField,
+};
+use std::{cmp, fmt::Debug};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Stores the changes of a `Vec<T>` as an edit script, instead of two copies
+/// of the whole vector.
+///
+/// The script is computed with the diff algorithm of Myers, so it is minimal.
+/// Only the changed ranges are stored, together with their old elements,
+/// which have to match when the diff is applied.
+///
+/// The generic argument is the element type, use it as
+/// `#[patch(diff = VecDiff<T>)]` on a field of type `Vec<T>`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VecDiff<T>
+where
+    T: PatchableField,
+{
+    /// Sorted by `index`, the ranges of two edits never overlap or touch.
+    edits: Vec<VecEdit<T>>,
+}
+
+/// A single edit of a `VecDiff`: the elements `old`, starting at `index` of
+/// the original vector, are replaced by `new`.
+///
+/// An insertion has no `old` elements, a deletion no `new` ones.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VecEdit<T>
+where
+    T: PatchableField,
+{
+    index: usize,
+    old: Vec<T>,
+    new: Vec<T>,
+}
+
+impl<T> VecEdit<T>
+where
+    T: PatchableField,
+{
+    /// The position of the first replaced element in the original vector.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The elements that are replaced, they have to match when applying.
+    pub fn removed(&self) -> &[T] {
+        &self.old
+    }
+
+    /// The elements that are put in place of the `removed` ones.
+    pub fn inserted(&self) -> &[T] {
+        &self.new
+    }
+
+    /// An edit that replaces `removed` at `index` by `inserted`.
+    pub(crate) fn replace(index: usize, removed: Vec<T>, inserted: Vec<T>) -> VecEdit<T> {
+        VecEdit {
+            index,
+            old: removed,
+            new: inserted,
+        }
+    }
+
+    /// The range of the edit in the original vector.
+    fn end(&self) -> usize {
+        self.index + self.old.len()
+    }
+}
+
+impl<T> VecDiff<T>
+where
+    T: PatchableField + Clone + PartialEq,
+{
+    /// The edits of this diff, sorted by their index.
+    pub fn edits(&self) -> &[VecEdit<T>] {
+        &self.edits
+    }
+
+    /// A diff out of `edits`, which have to be sorted and must not overlap.
+    pub(crate) fn from_edits(edits: Vec<VecEdit<T>>) -> VecDiff<T> {
+        VecDiff { edits }
+    }
+
+    /// Removes the elements at the start and end of each edit that are not
+    /// changed, and edits that do not change anything.
+    fn normalize(&mut self) {
+        for edit in &mut self.edits {
+            let prefix = edit
+                .old
+                .iter()
+                .zip(&edit.new)
+                .take_while(|(old, new)| old.compare(new))
+                .count();
+            edit.old.drain(..prefix);
+            edit.new.drain(..prefix);
+            edit.index += prefix;
+
+            let suffix = edit
+                .old
+                .iter()
+                .rev()
+                .zip(edit.new.iter().rev())
+                .take_while(|(old, new)| old.compare(new))
+                .count();
+            edit.old.truncate(edit.old.len() - suffix);
+            edit.new.truncate(edit.new.len() - suffix);
+        }
+        self.edits
+            .retain(|edit| !edit.old.is_empty() || !edit.new.is_empty());
+    }
+}
+
+/// The matching elements of `old` and `new` as pairs of indices, in order.
+///
+/// This is the diff algorithm of Myers, see "An O(ND) Difference Algorithm
+/// and Its Variations". Elements are equal if `PatchableField::compare` says
+/// so.
+fn matches<T>(old: &[T], new: &[T]) -> Vec<(usize, usize)>
+where
+    T: PatchableField + PartialEq,
+{
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    // furthest reaching x on diagonal `k`, stored at `k + offset`
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=(n + m) {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize].compare(&new[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let i = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            result.push((x as usize, y as usize));
+        }
+        if d == 0 {
+            break;
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    result.reverse();
+    result
+}
+
+/// `original`, the elements starting at `start`, with `edits` applied.
+fn replaced<T>(original: &[&T], start: usize, edits: &[&VecEdit<T>]) -> Vec<T>
+where
+    T: PatchableField + Clone,
+{
+    let mut result = Vec::new();
+    let mut position = start;
+    for edit in edits {
+        result.extend(
+            original[position - start..edit.index - start]
+                .iter()
+                .map(|e| (*e).clone()),
+        );
+        result.extend(edit.new.iter().cloned());
+        position = edit.end();
+    }
+    result.extend(original[position - start..].iter().map(|e| (*e).clone()));
+    result
+}
+
+/// An edit of `self` or `rhs` while merging, with its range in the vector
+/// between both.
+struct Span {
+    start: usize,
+    end: usize,
+    is_rhs: bool,
+    edit: usize,
+}
+
+impl<T> Diff for VecDiff<T>
+where
+    T: Clone + PartialEq + PatchableField + Debug,
+{
+    type DiffResult = Vec<VecEdit<T>>;
+    type Object = Vec<T>;
+
+    fn new(old: &Vec<T>, new: &Vec<T>) -> Self {
+        let mut edits = Vec::new();
+        let (mut x, mut y) = (0, 0);
+        let end = (old.len(), new.len());
+        for (match_x, match_y) in matches(old, new).into_iter().chain(Some(end)) {
+            if match_x > x || match_y > y {
+                edits.push(VecEdit {
+                    index: x,
+                    old: old[x..match_x].to_vec(),
+                    new: new[y..match_y].to_vec(),
+                });
+            }
+            x = match_x + 1;
+            y = match_y + 1;
+        }
+        VecDiff { edits }
+    }
+
+    fn changes_object(&self, old: &Vec<T>) -> bool {
+        self.contains_change() && self.applies_cleanly(old).is_ok()
+    }
+
+    fn contains_change(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Combines both edit scripts into one.
+    ///
+    /// Fails if `rhs` expects other elements than the ones this diff puts
+    /// into the vector.
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        // the edits of both diffs in the coordinates of the vector between them
+        let mut spans = Vec::new();
+        let mut shift = 0isize;
+        for (i, edit) in self.edits.iter().enumerate() {
+            let start = (edit.index as isize + shift) as usize;
+            spans.push(Span {
+                start,
+                end: start + edit.new.len(),
+                is_rhs: false,
+                edit: i,
+            });
+            shift += edit.new.len() as isize - edit.old.len() as isize;
+        }
+        for (i, edit) in rhs.edits.iter().enumerate() {
+            spans.push(Span {
+                start: edit.index,
+                end: edit.end(),
+                is_rhs: true,
+                edit: i,
+            });
+        }
+        spans.sort_by_key(|span| (span.start, span.is_rhs));
+
+        // edits that overlap or touch are merged into one
+        let mut groups: Vec<Vec<Span>> = Vec::new();
+        let mut group_end = 0;
+        for span in spans {
+            match groups.last_mut() {
+                Some(group) if span.start <= group_end => {
+                    group_end = cmp::max(group_end, span.end);
+                    group.push(span);
+                }
+                _ => {
+                    group_end = span.end;
+                    groups.push(vec![span]);
+                }
+            }
+        }
+
+        let mut edits = Vec::new();
+        // the shift of the edits of `self` in front of the current group
+        let mut shift = 0isize;
+        for group in groups {
+            let start = group.iter().map(|span| span.start).min().expect("a span");
+            let end = group.iter().map(|span| span.end).max().expect("a span");
+
+            // the elements of the vector between both diffs
+            let mut between: Vec<Option<&T>> = vec![None; end - start];
+            for span in &group {
+                let elements = if span.is_rhs {
+                    &rhs.edits[span.edit].old
+                } else {
+                    &self.edits[span.edit].new
+                };
+                for (slot, element) in between[span.start - start..].iter_mut().zip(elements) {
+                    match slot {
+                        Some(known) if !known.compare(element) => return Err(()),
+                        _ => *slot = Some(element),
+                    }
+                }
+            }
+            let between: Vec<&T> = between
+                .into_iter()
+                .map(|element| element.expect("every element is part of an edit"))
+                .collect();
+
+            // replaces the ranges of the edits of one diff in `between`
+            let replace = |is_rhs: bool, elements: fn(&VecEdit<T>) -> &[T]| {
+                let mut result = Vec::new();
+                let mut position = start;
+                for span in group.iter().filter(|span| span.is_rhs == is_rhs) {
+                    let edit = if is_rhs {
+                        &rhs.edits[span.edit]
+                    } else {
+                        &self.edits[span.edit]
+                    };
+                    result.extend(
+                        between[position - start..span.start - start]
+                            .iter()
+                            .cloned()
+                            .cloned(),
+                    );
+                    result.extend(elements(edit).iter().cloned());
+                    position = span.end;
+                }
+                result.extend(between[position - start..].iter().cloned().cloned());
+                result
+            };
+
+            edits.push(VecEdit {
+                index: (start as isize - shift) as usize,
+                old: replace(false, |edit| &edit.old),
+                new: replace(true, |edit| &edit.new),
+            });
+            for span in group.iter().filter(|span| !span.is_rhs) {
+                let edit = &self.edits[span.edit];
+                shift += edit.new.len() as isize - edit.old.len() as isize;
+            }
+        }
+
+        self.edits = edits;
+        self.normalize();
+        Ok(())
+    }
+
+    /// Reports the first edit whose old elements do not match, see `check`
+    /// for all of them.
+    fn applies_cleanly(&self, obj: &Vec<T>) -> Result<(), MismatchError> {
+        self.check(obj)
+            .map_err(|e| e.mismatches().first().cloned().expect("an error"))
+    }
+
+    /// Checks the old elements of every edit.
+    ///
+    /// The name of a mismatch is the index of the edit in the vector, the
+    /// patch puts the name of the field in front of it, e.g. `items.3`.
+    fn check(&self, obj: &Vec<T>) -> Result<(), MultipleMismatchError> {
+        let mut error = MultipleMismatchError::new();
+        for edit in &self.edits {
+            let received = &obj[cmp::min(edit.index, obj.len())..cmp::min(edit.end(), obj.len())];
+            let matches = edit.end() <= obj.len()
+                && received.len() == edit.old.len()
+                && received
+                    .iter()
+                    .zip(&edit.old)
+                    .all(|(received, old)| received.compare(old));
+            if !matches {
+                error.add_error(MismatchError::new_element(
+                    edit.index.to_string(),
+                    format!("{:?}", edit.old),
+                    format!("{:?}", received),
+                    MismatchType::PatchOldValue,
+                ));
+            }
+        }
+        if error.has_errors() {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply_into(&self, obj: &mut Vec<T>) -> Result<(), MismatchError> {
+        self.applies_cleanly(obj)?;
+        // back to front, so the indices of the remaining edits stay valid
+        for edit in self.edits.iter().rev() {
+            obj.splice(edit.index..edit.end(), edit.new.iter().cloned());
+        }
+        Ok(())
+    }
+}
+
+impl<T> Invert for VecDiff<T>
+where
+    T: Clone + PartialEq + PatchableField + Debug,
+{
+    /// Swaps the old and new elements of every edit.
+    ///
+    /// The indices are moved to the changed vector, by the elements the edits
+    /// in front of them insert or remove.
+    fn invert(&self) -> Self {
+        let mut shift = 0isize;
+        let edits = self
+            .edits
+            .iter()
+            .map(|edit| {
+                let inverted = VecEdit {
+                    index: (edit.index as isize + shift) as usize,
+                    old: edit.new.clone(),
+                    new: edit.old.clone(),
+                };
+                shift += edit.new.len() as isize - edit.old.len() as isize;
+                inverted
+            })
+            .collect();
+        VecDiff { edits }
+    }
+}
+
+impl<T> Transform for VecDiff<T>
+where
+    T: Clone + PartialEq + PatchableField + Debug,
+{
+    /// Moves the edits behind the elements `concurrent` inserts or removes in
+    /// front of them.
+    ///
+    /// Edits of both diffs that overlap are a conflict. The ones of the diff
+    /// with the priority replace what the other diff put there, the others
+    /// are dropped. Insertions at the same index are both kept, the one with
+    /// the priority first.
+    fn transform(&self, concurrent: &Self, priority: Priority) -> Self {
+        let ours = priority == Priority::Ours;
+        let (n, theirs) = (self.edits.len(), &concurrent.edits);
+
+        // overlapping edits get the same group, the ones of `concurrent`
+        // are numbered after the ones of `self`
+        let mut group: Vec<usize> = (0..n + theirs.len()).collect();
+        for (i, edit) in self.edits.iter().enumerate() {
+            for (j, other) in theirs.iter().enumerate() {
+                let (from, to) = (group[n + j], group[i]);
+                if edit.index < other.end() && other.index < edit.end() && from != to {
+                    for g in group.iter_mut().filter(|g| **g == from) {
+                        *g = to;
+                    }
+                }
+            }
+        }
+        // the edits of `edits` in group `g`
+        fn members<'a, T>(
+            group: &[usize],
+            g: usize,
+            edits: &'a [VecEdit<T>],
+            offset: usize,
+        ) -> Vec<&'a VecEdit<T>>
+        where
+            T: PatchableField,
+        {
+            (0..edits.len())
+                .filter(|i| group[offset + i] == g)
+                .map(|i| &edits[i])
+                .collect()
+        }
+        // the elements the edits of `concurrent` in front of `index` add
+        let shift = |index: usize, insertion: bool| -> isize {
+            theirs
+                .iter()
+                .filter(|other| {
+                    other.end() < index
+                        || (other.end() == index && !(insertion && other.old.is_empty() && ours))
+                })
+                .map(|other| other.new.len() as isize - other.old.len() as isize)
+                .sum()
+        };
+
+        let mut edits: Vec<VecEdit<T>> = Vec::new();
+        let mut conflicts = Vec::new();
+        for (i, edit) in self.edits.iter().enumerate() {
+            let other = members(&group, group[i], theirs, n);
+            if other.is_empty() {
+                edits.push(VecEdit {
+                    index: (edit.index as isize + shift(edit.index, edit.old.is_empty())) as usize,
+                    old: edit.old.clone(),
+                    new: edit.new.clone(),
+                });
+                continue;
+            }
+            if !ours || conflicts.contains(&group[i]) {
+                continue;
+            }
+            conflicts.push(group[i]);
+
+            let own = members(&group, group[i], &self.edits, 0);
+            let all = || own.iter().chain(&other);
+            let start = all().map(|edit| edit.index).min().expect("an edit");
+            let end = all().map(|edit| edit.end()).max().expect("an edit");
+            // the elements of the original vector, every one is part of an edit
+            let mut original: Vec<Option<&T>> = vec![None; end - start];
+            for edit in all() {
+                for (slot, element) in original[edit.index - start..].iter_mut().zip(&edit.old) {
+                    *slot = Some(element);
+                }
+            }
+            let original: Vec<&T> = original
+                .into_iter()
+                .map(|element| element.expect("every element is part of an edit"))
+                .collect();
+            edits.push(VecEdit {
+                index: (start as isize + shift(start, false)) as usize,
+                old: replaced(&original, start, &other),
+                new: replaced(&original, start, &own),
+            });
+        }
+
+        // edits that touch are joined, like the ones of `new`
+        let mut joined: Vec<VecEdit<T>> = Vec::new();
+        for edit in edits {
+            match joined.last_mut() {
+                Some(last) if last.end() == edit.index => {
+                    last.old.extend(edit.old);
+                    last.new.extend(edit.new);
+                }
+                _ => joined.push(edit),
+            }
+        }
+        let mut diff = VecDiff { edits: joined };
+        diff.normalize();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert::tests::*;
+
+    fn diff(old: &str, new: &str) -> VecDiff<char> {
+        Diff::new(&old.chars().collect(), &new.chars().collect())
+    }
+
+    fn apply(diff: &VecDiff<char>, obj: &str) -> String {
+        let mut obj = obj.chars().collect();
+        diff.apply_into(&mut obj).expect("applies cleanly");
+        obj.into_iter().collect()
+    }
+
+    #[test]
+    fn test_sync() {
+        assert_sync::<VecDiff<u8>>();
+        assert_sync::<VecDiff<String>>();
+    }
+
+    #[test]
+    fn test_send() {
+        assert_send::<VecDiff<u8>>();
+        assert_send::<VecDiff<String>>();
+    }
+
+    #[test]
+    fn test_minimal_script() {
+        // the example of the paper, the shortest script has 5 edits
+        let diff = diff("abcabba", "cbabac");
+
+        let changed: usize = diff
+            .edits()
+            .iter()
+            .map(|edit| edit.removed().len() + edit.inserted().len())
+            .sum();
+        assert_eq!(changed, 5);
+        assert_eq!(apply(&diff, "abcabba"), "cbabac");
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        for (old, new) in &[
+            ("", ""),
+            ("", "abc"),
+            ("abc", ""),
+            ("abc", "abc"),
+            ("abc", "xyz"),
+        ] {
+            assert_eq!(apply(&diff(old, new), old), *new);
+        }
+        assert!(!diff("abc", "abc").contains_change());
+    }
+
+    #[test]
+    fn test_invert() {
+        for (old, new) in &[("abcabba", "cbabac"), ("abcdef", "aXYZf"), ("", "abc")] {
+            let diff = diff(old, new);
+
+            assert_eq!(apply(&diff.invert(), new), *old);
+            assert_eq!(diff.invert().invert(), diff);
+        }
+    }
+
+    #[test]
+    fn test_transform() {
+        let moved = diff("abcdef", "aXcdef").transform(&diff("abcdef", "abcdYYf"), Priority::Ours);
+        assert_eq!(apply(&moved, "abcdYYf"), "aXcdYYf");
+
+        // the edit that loses a conflict is dropped
+        let dropped = diff("abcdef", "abZdef").transform(&diff("abcdef", "aWef"), Priority::Theirs);
+        assert!(!dropped.contains_change());
+    }
+
+    #[test]
+    fn test_merge() {
+        let cases = [
+            ("abcdef", "abXdef", "abXdeY"),
+            ("abcdef", "aXYZf", "aXf"),
+            ("abcdef", "abcdef", "fedcba"),
+            ("abc", "", "xyz"),
+            ("abcdef", "bcdefg", "cdefgh"),
+            ("aaaa", "aabaa", "abaaba"),
+        ];
+        for (a, b, c) in &cases {
+            let mut merged = diff(a, b);
+
+            assert_eq!(merged.merge(&diff(b, c)), Ok(()));
+            assert_eq!(apply(&merged, a), *c, "{} -> {} -> {}", a, b, c);
+        }
+    }
+
+    #[test]
+    fn test_merge_conflict() {
+        let mut first = diff("abc", "aXc");
+
+        assert_eq!(first.merge(&diff("abc", "aYc")), Err(()));
+    }
+}
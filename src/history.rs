@@ -0,0 +1,386 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Undo and redo of patches, see `UndoStack`.
+
+use crate::{mismatch::MultipleMismatchError, traits::InvertPatch};
+use std::collections::VecDeque;
+
+/// The history of the patches applied to an object.
+///
+/// Every step of the history is a list of patches, usually a single one.
+/// `undo` applies the inverted patches of the last step, see
+/// `InvertPatch::invert`, `redo` applies them again.
+///
+/// Patches recorded between `begin_group` and `end_group` form a single
+/// step. With `set_coalescing`, a patch that sets the same fields as the
+/// previous one is merged into it instead, e.g. to undo typing a word at
+/// once. `with_limit` drops the oldest steps once there are too many.
+#[derive(Debug, Clone)]
+pub struct UndoStack<P>
+where
+    P: InvertPatch,
+{
+    undo: VecDeque<Vec<P>>,
+    redo: Vec<Vec<P>>,
+    /// The patches of the open group.
+    group: Vec<P>,
+    /// How often `begin_group` was called without `end_group`.
+    group_depth: usize,
+    limit: Option<usize>,
+    coalescing: bool,
+}
+
+impl<P> Default for UndoStack<P>
+where
+    P: InvertPatch,
+{
+    fn default() -> Self {
+        UndoStack::new()
+    }
+}
+
+impl<P> UndoStack<P>
+where
+    P: InvertPatch,
+{
+    /// An empty history without a limit.
+    pub fn new() -> UndoStack<P> {
+        UndoStack {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: Vec::new(),
+            group_depth: 0,
+            limit: None,
+            coalescing: false,
+        }
+    }
+
+    /// An empty history that keeps at most `limit` steps to undo.
+    pub fn with_limit(limit: usize) -> UndoStack<P> {
+        let mut stack = UndoStack::new();
+        stack.limit = Some(limit);
+        stack
+    }
+
+    /// The maximum number of steps to undo, `None` if there is no limit.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Changes the limit, dropping the oldest steps if there are more.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+        self.truncate();
+    }
+
+    /// True if consecutive patches are coalesced, see `Patch::coalesce`.
+    pub fn is_coalescing(&self) -> bool {
+        self.coalescing
+    }
+
+    /// Turns coalescing of consecutive patches on or off, off by default.
+    ///
+    /// Only the last step is coalesced, and only if it consists of a single
+    /// patch. Within a group, a patch is coalesced with the previous patch of
+    /// the group.
+    pub fn set_coalescing(&mut self, coalescing: bool) {
+        self.coalescing = coalescing;
+    }
+
+    /// Applies `patch` to `obj` and records it.
+    ///
+    /// Nothing is recorded if the patch fails.
+    pub fn apply(&mut self, patch: P, obj: &mut P::Target) -> Result<(), MultipleMismatchError> {
+        patch.apply(obj)?;
+        self.push(patch);
+        Ok(())
+    }
+
+    /// Records a patch that was applied already.
+    ///
+    /// Empty patches are ignored. Clears the steps to redo.
+    pub fn push(&mut self, patch: P) {
+        if patch.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        if self.group_depth > 0 {
+            if let Some(last) = self.group.last_mut() {
+                if self.coalescing && last.coalesce(&patch) {
+                    return;
+                }
+            }
+            self.group.push(patch);
+            return;
+        }
+        if let Some([last]) = self.undo.back_mut().map(Vec::as_mut_slice) {
+            if self.coalescing && last.coalesce(&patch) {
+                return;
+            }
+        }
+        self.undo.push_back(vec![patch]);
+        self.truncate();
+    }
+
+    /// Starts a group, all patches recorded until `end_group` are undone
+    /// in a single step.
+    ///
+    /// Groups can be nested, only the outermost one forms a step.
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Ends the group started by `begin_group`.
+    ///
+    /// Does nothing if there is no open group.
+    pub fn end_group(&mut self) {
+        match self.group_depth {
+            0 => {}
+            1 => {
+                self.group_depth = 0;
+                if !self.group.is_empty() {
+                    let group = std::mem::take(&mut self.group);
+                    self.undo.push_back(group);
+                    self.truncate();
+                }
+            }
+            _ => self.group_depth -= 1,
+        }
+    }
+
+    /// True if a group is open.
+    pub fn is_grouping(&self) -> bool {
+        self.group_depth > 0
+    }
+
+    /// True if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.group.is_empty()
+    }
+
+    /// True if there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// The number of steps to undo, an open group is not counted.
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// The number of steps to redo.
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// The patches of the steps to undo, the oldest first.
+    pub fn steps(&self) -> impl Iterator<Item = &[P]> {
+        self.undo.iter().map(Vec::as_slice)
+    }
+
+    /// Forgets all steps, and the open group.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group.clear();
+        self.group_depth = 0;
+    }
+
+    /// Undoes the last step, an open group is ended first.
+    ///
+    /// Returns false if there is nothing to undo. If an inverted patch does
+    /// not apply, `obj` is restored and the step stays in the history.
+    pub fn undo(&mut self, obj: &mut P::Target) -> Result<bool, MultipleMismatchError> {
+        if self.group_depth > 0 {
+            self.group_depth = 1;
+            self.end_group();
+        }
+        let step = match self.undo.pop_back() {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+        let inverted: Vec<P> = step.iter().rev().map(InvertPatch::invert).collect();
+        match Self::apply_all(&inverted, obj) {
+            Ok(()) => {
+                self.redo.push(step);
+                Ok(true)
+            }
+            Err(e) => {
+                self.undo.push_back(step);
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies the last undone step again.
+    ///
+    /// Returns false if there is nothing to redo. If a patch does not apply,
+    /// `obj` is restored and the step stays in the history.
+    pub fn redo(&mut self, obj: &mut P::Target) -> Result<bool, MultipleMismatchError> {
+        let step = match self.redo.pop() {
+            Some(step) => step,
+            None => return Ok(false),
+        };
+        match Self::apply_all(&step, obj) {
+            Ok(()) => {
+                self.undo.push_back(step);
+                self.truncate();
+                Ok(true)
+            }
+            Err(e) => {
+                self.redo.push(step);
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies all `patches` in order, or none of them.
+    fn apply_all(patches: &[P], obj: &mut P::Target) -> Result<(), MultipleMismatchError> {
+        for (applied, patch) in patches.iter().enumerate() {
+            if let Err(e) = patch.apply(obj) {
+                // the patches applied so far were just applied, so their
+                // inversions apply as well
+                for patch in patches[..applied].iter().rev() {
+                    let _ = patch.invert().apply(obj);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the oldest steps above the limit.
+    fn truncate(&mut self) {
+        if let Some(limit) = self.limit {
+            while self.undo.len() > limit {
+                self.undo.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diff::{CopyDiff, Diff, Invert},
+        mismatch::{MismatchError, MismatchType},
+        traits::{Base, Patch},
+    };
+
+    /// A hand written patch of a single `u32`.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Counter(Option<CopyDiff<u32>>);
+
+    impl Base<Result<(), MultipleMismatchError>> for Counter {
+        type Target = u32;
+
+        const MAX_FIELDS: usize = 1;
+
+        fn is_complete(&self) -> bool {
+            self.0.is_some()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.0.is_none()
+        }
+
+        fn count(&self) -> u32 {
+            self.0.is_some() as u32
+        }
+
+        fn apply(&self, obj: &mut u32) -> Result<(), MultipleMismatchError> {
+            Patch::check(self, obj)?;
+            if let Some(diff) = &self.0 {
+                let _ = diff.apply_into(obj);
+            }
+            Ok(())
+        }
+    }
+
+    impl Patch for Counter {
+        fn cleanup(&mut self) -> bool {
+            false
+        }
+
+        fn is_correct_target(&self, _obj: &u32) -> Result<(), MultipleMismatchError> {
+            Ok(())
+        }
+
+        fn is_same_target(&self, _other: &Self) -> bool {
+            true
+        }
+
+        fn can_apply_cleanly(&self, obj: &u32) -> Result<(), MultipleMismatchError> {
+            match &self.0 {
+                Some(diff) => diff.check(obj),
+                None => Ok(()),
+            }
+        }
+
+        fn coalesce(&mut self, next: &Self) -> bool {
+            match (&mut self.0, &next.0) {
+                (Some(lhs), Some(rhs)) => lhs.merge(rhs).is_ok(),
+                _ => false,
+            }
+        }
+    }
+
+    impl InvertPatch for Counter {
+        fn invert(&self) -> Self {
+            Counter(self.0.as_ref().map(Invert::invert))
+        }
+    }
+
+    fn change(old: u32, new: u32) -> Counter {
+        Counter(Some(Diff::new(&old, &new)))
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut stack = UndoStack::new();
+        let mut obj = 0;
+        assert_eq!(stack.apply(change(0, 1), &mut obj), Ok(()));
+        assert_eq!(stack.apply(change(1, 2), &mut obj), Ok(()));
+
+        assert_eq!(stack.undo(&mut obj), Ok(true));
+        assert_eq!(obj, 1);
+        assert_eq!(stack.undo(&mut obj), Ok(true));
+        assert_eq!(obj, 0);
+        assert_eq!(stack.undo(&mut obj), Ok(false));
+
+        assert_eq!(stack.redo(&mut obj), Ok(true));
+        assert_eq!(obj, 1);
+        assert_eq!((stack.undo_count(), stack.redo_count()), (1, 1));
+    }
+
+    #[test]
+    fn test_failed_group_is_rolled_back() {
+        let mut stack = UndoStack::new();
+        let mut obj = 0;
+        stack.begin_group();
+        assert_eq!(stack.apply(change(0, 1), &mut obj), Ok(()));
+        assert_eq!(stack.apply(change(1, 2), &mut obj), Ok(()));
+        stack.end_group();
+
+        // the first inverted patch, 2 -> 1, applies, the second one does not
+        stack.undo.back_mut().unwrap()[0] = change(5, 7);
+        let error = stack.undo(&mut obj).unwrap_err();
+
+        assert_eq!(
+            error.mismatches(),
+            [MismatchError::new(
+                "",
+                "7",
+                "1",
+                MismatchType::PatchOldValue
+            )]
+        );
+        assert_eq!(obj, 2);
+        assert_eq!(stack.undo_count(), 1);
+    }
+}
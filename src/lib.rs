@@ -9,6 +9,12 @@
 //! One for generating a `partial` Variant of an object. (Inspired by typescript
 //! ```Partial<Object>```)
 //!
+//! Derive it with `#[derive(Partial)]` to get a `Partial<Name>` struct.
+//!
+//! The other one for generating a `patch`, that stores the difference between
+//! two states of an object. Derive it with `#[derive(Patch)]` to get a
+//! `<Name>Patch` struct.
+//!
 //! If you want a peek at what the generated struct should to look like, have a
 //! look at tests/template_partial.rs and tests/template_patch.rs
 
@@ -18,14 +24,20 @@
 
 extern crate macro_impl;
 
+pub use macro_impl::{Partial, Patch};
+
+/// Used by the serde derives of the generated types.
 #[cfg(feature = "serde")]
-extern crate serde;
+#[doc(hidden)]
+pub use serde;
 
 pub mod mismatch;
 pub mod patchable;
 pub mod traits;
 
+pub mod conflict;
 pub mod diff;
+pub mod history;
 
 #[cfg(test)]
 pub(crate) mod assert;
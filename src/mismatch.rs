@@ -59,6 +59,7 @@ impl<Incompletable> Error for IncompleteError<Incompletable> where Incompletable
 //#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MismatchError {
     field_name: &'static str,
+    path: String,
     /// The expected value for the operation to succeed
     pub expected: String,
     /// The actual value during the operation
@@ -82,6 +83,32 @@ impl MismatchError {
     {
         MismatchError {
             field_name,
+            path: field_name.to_string(),
+            expected: expected.into(),
+            received: received.into(),
+            mismatch_type,
+        }
+    }
+
+    /// creates a new MismatchData for an element of a field, e.g. an index of
+    /// a `Vec` or a key of a map
+    ///
+    /// The field name stays empty until the patch fills it in, see
+    /// `prefix_field`.
+    pub fn new_element<P, E, R>(
+        element: P,
+        expected: E,
+        received: R,
+        mismatch_type: MismatchType,
+    ) -> MismatchError
+    where
+        P: Into<String>,
+        E: Into<String>,
+        R: Into<String>,
+    {
+        MismatchError {
+            field_name: "",
+            path: element.into(),
             expected: expected.into(),
             received: received.into(),
             mismatch_type,
@@ -89,16 +116,57 @@ impl MismatchError {
     }
 
     /// getter for the field name
+    ///
+    /// For mismatches of nested patches, this is the field of the outermost
+    /// patch. See `path()` for the full path.
     pub fn name(&self) -> &'static str {
         self.field_name
     }
+
+    /// getter for the path of the mismatch
+    ///
+    /// Fields of nested patches and elements of fields are separated by dots,
+    /// e.g. `address.city` or `songs.1`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// puts `prefix` in front of the path, separated by a dot.
+    ///
+    /// An empty path becomes `prefix`. The field name stays the same.
+    pub fn prefix_name(&mut self, prefix: &str) {
+        self.path = if self.path.is_empty() {
+            prefix.to_string()
+        } else {
+            format!("{}.{}", prefix, self.path)
+        };
+    }
+
+    /// puts `field` in front of the path and makes it the field name.
+    ///
+    /// A `Diff` does not know which field it belongs to, the generated patch
+    /// uses this to fill in the name.
+    pub fn prefix_field(&mut self, field: &'static str) {
+        self.prefix_name(field);
+        self.field_name = field;
+    }
+
+    /// getter for the type of the mismatch
+    pub fn mismatch_type(&self) -> MismatchType {
+        self.mismatch_type
+    }
+
+    /// setter for the type of the mismatch
+    pub fn set_mismatch_type(&mut self, mismatch_type: MismatchType) {
+        self.mismatch_type = mismatch_type;
+    }
 }
 impl fmt::Display for MismatchError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
             "{} - field {}: expected {}, got {}",
-            self.mismatch_type, self.field_name, self.expected, self.received
+            self.mismatch_type, self.path, self.expected, self.received
         )
     }
 }
@@ -112,12 +180,20 @@ pub enum MismatchType {
     /// The patch could not apply, because the expected value does not match the
     /// `old_value` of the patch
     PatchOldValue,
+    /// The patch could not apply, because the object is another variant of an
+    /// enum than the patch expects
+    VariantMismatch,
+    /// Two patches could not be merged, because the second one expects other
+    /// values than the first one sets
+    MergeConflict,
 }
 impl fmt::Display for MismatchType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let desc = match self {
             MismatchType::ObjectID => "Object id didn't match patch id",
             MismatchType::PatchOldValue => "Current object valie did not match old patch value",
+            MismatchType::VariantMismatch => "Object variant didn't match patch variant",
+            MismatchType::MergeConflict => "Patch changes didn't fit together",
         };
         write!(f, "{}", desc)
     }
@@ -126,9 +202,11 @@ impl fmt::Display for MismatchType {
 /// Funcgtions that require specific fields to be a specific value can return
 /// this error in case the value differed.
 ///
-/// There are two types of mismatches that can hapen:
+/// There are four types of mismatches that can hapen:
 /// - because an id field mismatched
 /// - because the expected value does not match the `old_value` of the patch
+/// - because the object is another variant than the patch expects
+/// - because two merged patches conflict
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 //#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MultipleMismatchError {
@@ -169,6 +247,25 @@ impl MultipleMismatchError {
     pub fn merge(&mut self, rhs: &MultipleMismatchError) {
         self.mismatches.extend(rhs.mismatches.iter().cloned())
     }
+
+    /// all mismatches stored inside, in the order they were added
+    pub fn mismatches(&self) -> &[MismatchError] {
+        &self.mismatches
+    }
+
+    /// calls `MismatchError::prefix_name` on all mismatches stored inside
+    pub fn prefix_names(&mut self, prefix: &str) {
+        for mismatch in &mut self.mismatches {
+            mismatch.prefix_name(prefix);
+        }
+    }
+
+    /// calls `MismatchError::prefix_field` on all mismatches stored inside
+    pub fn prefix_fields(&mut self, field: &'static str) {
+        for mismatch in &mut self.mismatches {
+            mismatch.prefix_field(field);
+        }
+    }
 }
 
 impl Error for MultipleMismatchError {}
@@ -192,6 +289,54 @@ mod tests {
     #[derive(Debug)]
     struct DummyIncompletable {}
 
+    #[test]
+    fn test_prefix_names() {
+        let mut error = MultipleMismatchError::new();
+        error.add_error(MismatchError::new(
+            "",
+            "1",
+            "2",
+            MismatchType::PatchOldValue,
+        ));
+        error.add_error(MismatchError::new(
+            "city",
+            "a",
+            "b",
+            MismatchType::PatchOldValue,
+        ));
+
+        error.prefix_names("address");
+
+        let paths: Vec<&str> = error.mismatches().iter().map(MismatchError::path).collect();
+        assert_eq!(paths, ["address", "address.city"]);
+        let names: Vec<&str> = error.mismatches().iter().map(MismatchError::name).collect();
+        assert_eq!(names, ["", "city"]);
+    }
+
+    #[test]
+    fn test_prefix_fields() {
+        let mut error = MultipleMismatchError::new();
+        error.add_error(MismatchError::new_element(
+            "3",
+            "1",
+            "2",
+            MismatchType::PatchOldValue,
+        ));
+        error.add_error(MismatchError::new(
+            "city",
+            "a",
+            "b",
+            MismatchType::PatchOldValue,
+        ));
+
+        error.prefix_fields("address");
+
+        let paths: Vec<&str> = error.mismatches().iter().map(MismatchError::path).collect();
+        assert_eq!(paths, ["address.3", "address.city"]);
+        let names: Vec<&str> = error.mismatches().iter().map(MismatchError::name).collect();
+        assert_eq!(names, ["address", "address"]);
+    }
+
     #[test]
     fn test_sync() {
         assert_sync::<MultipleMismatchError>();
@@ -6,6 +6,11 @@
 
 //! Crate dealing will all different kinds of fields that can be `patchable``
 
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::Hash,
+};
+
 /// todo:
 pub trait PatchableField
 where
@@ -49,19 +54,22 @@ impl PatchableField for isize {}
 impl PatchableField for f32 {
     #[cfg(feature = "epsilon_compare")]
     fn compare(&self, rhs: &f32) -> bool {
-        (self - rhs).abs() < std::f32::EPSILON
+        (self - rhs).abs() < f32::EPSILON
     }
 }
 impl PatchableField for f64 {
     #[cfg(feature = "epsilon_compare")]
     fn compare(&self, rhs: &f64) -> bool {
-        (self - rhs).abs() < std::f64::EPSILON
+        (self - rhs).abs() < f64::EPSILON
     }
 }
 
 impl PatchableField for bool {}
 impl PatchableField for char {}
 impl PatchableField for String {}
+impl PatchableField for &str {}
+
+impl<T> PatchableField for &T where T: PatchableField {}
 
 impl<T> PatchableField for Option<T> where T: PatchableField + Clone {}
 
@@ -77,8 +85,25 @@ where
 
 impl<T> PatchableField for Vec<T> where T: PatchableField + Clone {}
 
-//TODO: how to deal with enums
-//impl PatchableField for enum {}
+impl<K, V> PatchableField for HashMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: PatchableField + Clone,
+{
+}
+
+impl<K, V> PatchableField for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: PatchableField + Clone,
+{
+}
+
+impl<T> PatchableField for HashSet<T> where T: Eq + Hash + Clone {}
+
+impl<T> PatchableField for BTreeSet<T> where T: Ord + Clone {}
+
+// enums implement `PatchableField` via `#[derive(Patch)]`
 
 //TODO:; array
 
@@ -110,6 +135,7 @@ where
 mod tests {
     use super::*;
 
+    #[allow(dead_code)]
     struct PatchableStruct {
         pub d1: u64,
         pub d2: String,
@@ -6,14 +6,24 @@
 
 //! Contains traits
 
-use crate::mismatch::MultipleMismatchError;
+use crate::{
+    conflict::{Conflict, ConflictResolver},
+    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
+};
 
 /// Extension trait that all structs S with `patch!(struct S {})` implement.
 pub trait PatchableExt
 where
     Self: Sized,
 {
-    // get_patch(self, rhs: Self) -> ;
+    /// The patch generated for this struct.
+    type Patch: Patch<Target = Self>;
+
+    /// Creates a patch that changes `self` into `rhs`.
+    ///
+    /// The id fields of the patch are taken from `self`. Fields that are equal
+    /// in both objects are left out.
+    fn get_patch(&self, rhs: &Self) -> Self::Patch;
     // get_patch_from_partial(self, rhs: Partial)
 }
 
@@ -60,12 +70,36 @@ pub trait Base<ErrorType> {
     //
     // does not care about the actual value set
     /* todo: fn same_fields_set(Self) -> bool; */
+}
 
-    //fn merge_into(&mut self, mergee: Self) -> ErrorType;
+/// Extension trait that all structs S with `#[derive(Partial)]` implement.
+pub trait PartialExt
+where
+    Self: Sized,
+{
+    /// The partial generated for this struct.
+    type Partial: Partial<Target = Self>;
 }
 
 /// Base Trait for all Partials.
-pub trait Partial: Base<()> + std::convert::TryFrom<<Self as Base<()>>::Target> {}
+pub trait Partial: Base<()> + std::convert::TryFrom<<Self as Base<()>>::Target> {
+    /// Builds the `Target` out of this partial.
+    ///
+    /// Fails if not all fields are set, see `is_complete`.
+    #[allow(clippy::result_unit_err)]
+    fn build(&self) -> Result<Self::Target, ()>;
+
+    /// add all attributes set here in to the given Partial
+    ///
+    /// If an attribute in here is already set in `obj`, then it gets
+    /// overwritten
+    fn merge_into(&self, obj: &mut Self);
+
+    /// check all attributes that are present on BOTH objects, if they
+    /// are equal. If there are no attributes present in both, will
+    /// return true.
+    fn is_partial_equal_existing(&self, obj: &Self) -> bool;
+}
 
 /// Trait for all Patches
 pub trait Patch: Base<Result<(), MultipleMismatchError>> {
@@ -99,6 +133,80 @@ pub trait Patch: Base<Result<(), MultipleMismatchError>> {
     /// Does not check the target, see `check()` for that.
     fn can_apply_cleanly(&self, obj: &Self::Target) -> Result<(), MultipleMismatchError>;
 
+    /// Merges `next`, a patch that is applied right after this one, into
+    /// this patch, so both become a single step, e.g. in an undo history.
+    ///
+    /// Only works if both patches have the same target and set the same
+    /// fields, the diffs of each field are merged with `Diff::merge`. The
+    /// extra fields of this patch are kept. Returns false and leaves this
+    /// patch unchanged if the patches can not be coalesced.
+    fn coalesce(&mut self, next: &Self) -> bool
+    where
+        Self: Sized;
+
+    /// Adds the changes of this patch on top of `target`, so `target`
+    /// represents both patches applied one after the other.
+    ///
+    /// Fields that are set in both patches are merged with `Diff::merge`,
+    /// fields that are only set in this patch are copied. Fails with a
+    /// `MismatchType::ObjectID` mismatch per id field if the patches have
+    /// different targets, and a `MismatchType::MergeConflict` per field that
+    /// does not merge. `target` is unchanged if an error is returned.
+    ///
+    /// The default implementation always fails with a single
+    /// `MismatchType::MergeConflict`, for patches that can not be merged.
+    fn merge_into(&self, _target: &mut Self) -> Result<(), MultipleMismatchError>
+    where
+        Self: Sized,
+    {
+        Err(unsupported("merged", MismatchType::MergeConflict))
+    }
+
+    /// Replaces the fields of `target` with the ones that are set in this
+    /// patch, the last writer wins.
+    ///
+    /// Only fails if the patches have different targets, like `merge_into`.
+    /// The default implementation always fails, like the one of `merge_into`.
+    fn overwrite_into(&self, _target: &mut Self) -> Result<(), MultipleMismatchError>
+    where
+        Self: Sized,
+    {
+        Err(unsupported("merged", MismatchType::MergeConflict))
+    }
+
+    /// Keeps the values this patch sets, but takes the old values it expects
+    /// from the current state of `obj`, see `Diff::rebase`.
+    ///
+    /// Fails if `obj` is not the target of this patch, or with the mismatches
+    /// of the fields that can not be rebased, named like the ones of `check`.
+    /// The patch is unchanged if an error is returned.
+    ///
+    /// The default implementation always fails with a single
+    /// `MismatchType::PatchOldValue`, for patches that can not be rebased.
+    fn rebase(&mut self, _obj: &Self::Target) -> Result<(), MultipleMismatchError> {
+        Err(unsupported("rebased", MismatchType::PatchOldValue))
+    }
+
+    /// Merges this patch with `other`, a patch made concurrently of the same
+    /// object, into one that makes the changes of both.
+    ///
+    /// Fields that only one of the patches sets are taken as they are. For
+    /// every field that both set, `resolver` decides which change is kept or
+    /// whether both are combined with `Diff::combine`. Fails with the
+    /// conflicts that stay unresolved, or with a `MismatchType::ObjectID`
+    /// conflict per id field if the patches have different targets.
+    ///
+    /// The default implementation always fails with a single
+    /// `MismatchType::MergeConflict` conflict of the whole object, without
+    /// asking `resolver`.
+    fn merge_concurrent<R>(&self, _other: &Self, _resolver: &mut R) -> Result<Self, Vec<Conflict>>
+    where
+        R: ConflictResolver + ?Sized,
+        Self: Sized,
+    {
+        Err(vec![Conflict::new("", MismatchType::MergeConflict, false)])
+    }
+
     /// Checks both `is_correct_target` and `can_apply_cleanly`.
     ///
     /// returns MultipleMismatchError that contains errors of both functions
@@ -118,3 +226,112 @@ pub trait Patch: Base<Result<(), MultipleMismatchError>> {
         }
     }
 }
+
+/// The error of the default implementations of `Patch`, for patches that do
+/// not support an operation.
+fn unsupported(operation: &str, mismatch_type: MismatchType) -> MultipleMismatchError {
+    let mut error = MultipleMismatchError::new();
+    error.add_error(MismatchError::new(
+        "",
+        format!("a patch that can be {}", operation),
+        format!("a patch that can not be {}", operation),
+        mismatch_type,
+    ));
+    error
+}
+
+/// Patches that can be undone, e.g. by `history::UndoStack`.
+///
+/// The derived patches implement it if the diffs of all their fields
+/// implement `diff::Invert`.
+pub trait InvertPatch: Patch {
+    /// The patch that undoes this one.
+    ///
+    /// Every field diff is inverted with `Invert::invert`, the id and extra
+    /// fields are kept. Applying the inverted patch to an object this patch
+    /// was applied to restores the object, e.g. to implement undo.
+    fn invert(&self) -> Self;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::OursWins;
+
+    /// A hand written patch that only uses the default implementations.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Toggle(Option<bool>);
+
+    impl Base<Result<(), MultipleMismatchError>> for Toggle {
+        type Target = bool;
+
+        const MAX_FIELDS: usize = 1;
+
+        fn is_complete(&self) -> bool {
+            self.0.is_some()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.0.is_none()
+        }
+
+        fn count(&self) -> u32 {
+            self.0.is_some() as u32
+        }
+
+        fn apply(&self, obj: &mut bool) -> Result<(), MultipleMismatchError> {
+            if let Some(value) = self.0 {
+                *obj = value;
+            }
+            Ok(())
+        }
+    }
+
+    impl Patch for Toggle {
+        fn cleanup(&mut self) -> bool {
+            false
+        }
+
+        fn is_correct_target(&self, _obj: &bool) -> Result<(), MultipleMismatchError> {
+            Ok(())
+        }
+
+        fn is_same_target(&self, _other: &Self) -> bool {
+            true
+        }
+
+        fn can_apply_cleanly(&self, _obj: &bool) -> Result<(), MultipleMismatchError> {
+            Ok(())
+        }
+
+        fn coalesce(&mut self, _next: &Self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_merging_is_not_supported_by_default() {
+        let mut patch = Toggle(Some(true));
+
+        let error = Toggle(Some(false)).merge_into(&mut patch).unwrap_err();
+        assert_eq!(
+            error.mismatches()[0].mismatch_type(),
+            MismatchType::MergeConflict
+        );
+        let error = Toggle(Some(false)).overwrite_into(&mut patch).unwrap_err();
+        assert_eq!(
+            error.mismatches()[0].mismatch_type(),
+            MismatchType::MergeConflict
+        );
+        let error = patch.rebase(&false).unwrap_err();
+        assert_eq!(
+            error.mismatches()[0].mismatch_type(),
+            MismatchType::PatchOldValue
+        );
+        assert_eq!(
+            Toggle(Some(false)).merge_concurrent(&patch, &mut OursWins),
+            Err(vec![Conflict::new("", MismatchType::MergeConflict, false)])
+        );
+        assert_eq!(patch, Toggle(Some(true)));
+    }
+}
@@ -0,0 +1,35 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Helpers shared by the integration tests.
+
+// not every test uses every helper
+#![allow(dead_code)]
+
+use derive_patch::traits::Patch;
+use std::fmt::Debug;
+
+/// Returns a copy of `obj` changed by `change`.
+pub fn changed<T, F>(obj: &T, change: F) -> T
+where
+    T: Clone,
+    F: FnOnce(&mut T),
+{
+    let mut changed = obj.clone();
+    change(&mut changed);
+    changed
+}
+
+/// Applies `patch` to a copy of `obj`.
+pub fn applied<P>(obj: &P::Target, patch: &P) -> P::Target
+where
+    P: Patch,
+    P::Target: Clone + Debug,
+{
+    let mut obj = obj.clone();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    obj
+}
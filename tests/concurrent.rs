@@ -0,0 +1,264 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Merging patches made concurrently of the same object with
+//! `merge_concurrent`.
+
+mod common;
+
+use common::{applied, changed};
+use derive_patch::{
+    conflict::{Conflict, OursWins, Resolution, Sum, TheirsWins},
+    diff::{NumericDistanceDiff, SetDiff},
+    mismatch::MismatchType,
+    traits::{Base, Patch, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::HashSet;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Shelf {
+    #[patch(diff = NumericDistanceDiff)]
+    count: i32,
+    place: String,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Account {
+    #[patch(id)]
+    id: u32,
+    name: String,
+    #[patch(diff = NumericDistanceDiff)]
+    balance: i64,
+    #[patch(diff = SetDiff<String>)]
+    flags: HashSet<String>,
+    #[patch(nested)]
+    shelf: Shelf,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Discount {
+    Percent { value: u8, note: String },
+    Fixed { cents: u32 },
+}
+
+fn account() -> Account {
+    Account {
+        id: 1,
+        name: "ann".to_string(),
+        balance: 100,
+        flags: HashSet::new(),
+        shelf: Shelf {
+            count: 3,
+            place: "A1".to_string(),
+        },
+    }
+}
+
+#[test]
+fn different_fields_merge_without_conflicts() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| a.name = "anna".to_string()));
+    let theirs = base.get_patch(&changed(&base, |a| {
+        a.flags.insert("vip".to_string());
+        a.shelf.place = "B2".to_string();
+    }));
+
+    let merged = ours.merge_concurrent(&theirs, &mut OursWins).unwrap();
+
+    let obj = applied(&base, &merged);
+    assert_eq!(obj.name, "anna");
+    assert!(obj.flags.contains("vip"));
+    assert_eq!(obj.shelf.place, "B2");
+}
+
+#[test]
+fn ours_and_theirs_win() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| a.name = "anna".to_string()));
+    let theirs = base.get_patch(&changed(&base, |a| a.name = "annie".to_string()));
+
+    let merged = ours.merge_concurrent(&theirs, &mut OursWins).unwrap();
+    assert_eq!(applied(&base, &merged).name, "anna");
+    let merged = ours.merge_concurrent(&theirs, &mut TheirsWins).unwrap();
+    assert_eq!(applied(&base, &merged).name, "annie");
+}
+
+#[test]
+fn sum_combines_numbers_sets_and_nested_patches() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| {
+        a.balance += 20;
+        a.flags.insert("vip".to_string());
+        a.shelf.count -= 1;
+    }));
+    let theirs = base.get_patch(&changed(&base, |a| {
+        a.balance -= 50;
+        a.flags.insert("new".to_string());
+        a.shelf.count += 5;
+        a.shelf.place = "C3".to_string();
+    }));
+
+    let merged = ours.merge_concurrent(&theirs, &mut Sum).unwrap();
+
+    let obj = applied(&base, &merged);
+    assert_eq!(obj.balance, 70);
+    assert_eq!(obj.flags.len(), 2);
+    assert_eq!(obj.shelf.count, 7);
+    assert_eq!(obj.shelf.place, "C3");
+}
+
+#[test]
+fn unresolved_conflicts_are_returned() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| {
+        a.name = "anna".to_string();
+        a.balance += 1;
+    }));
+    let theirs = base.get_patch(&changed(&base, |a| {
+        a.name = "annie".to_string();
+        a.balance += 2;
+    }));
+
+    let conflicts = ours.merge_concurrent(&theirs, &mut Sum).unwrap_err();
+
+    assert_eq!(
+        conflicts,
+        [Conflict::new("name", MismatchType::MergeConflict, false)]
+    );
+}
+
+#[test]
+fn nested_patches_merge_field_by_field() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| a.shelf.count = 4));
+    let theirs = base.get_patch(&changed(&base, |a| a.shelf.place = "B2".to_string()));
+
+    let merged = ours.merge_concurrent(&theirs, &mut OursWins).unwrap();
+
+    let obj = applied(&base, &merged);
+    assert_eq!(obj.shelf.count, 4);
+    assert_eq!(obj.shelf.place, "B2");
+}
+
+#[test]
+fn conflicts_of_nested_fields_are_named_with_the_path() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| {
+        a.shelf.count = 4;
+        a.shelf.place = "B2".to_string();
+    }));
+    let theirs = base.get_patch(&changed(&base, |a| {
+        a.shelf.count = 5;
+        a.shelf.place = "C3".to_string();
+    }));
+
+    let conflicts = ours.merge_concurrent(&theirs, &mut Sum).unwrap_err();
+    assert_eq!(
+        conflicts,
+        [Conflict::new(
+            "shelf.place",
+            MismatchType::MergeConflict,
+            false
+        )]
+    );
+
+    let mut asked = Vec::new();
+    let merged = ours
+        .merge_concurrent(&theirs, &mut |conflict: &Conflict| {
+            asked.push(conflict.field().to_string());
+            Resolution::Theirs
+        })
+        .unwrap();
+    assert_eq!(asked, ["shelf.count", "shelf.place"]);
+    assert_eq!(
+        applied(&base, &merged).shelf,
+        Shelf {
+            count: 5,
+            place: "C3".to_string(),
+        }
+    );
+}
+
+#[test]
+fn a_callback_decides_per_field() {
+    let base = account();
+    let ours = base.get_patch(&changed(&base, |a| {
+        a.name = "anna".to_string();
+        a.balance += 1;
+    }));
+    let theirs = base.get_patch(&changed(&base, |a| {
+        a.name = "annie".to_string();
+        a.balance += 2;
+    }));
+    let mut asked = Vec::new();
+
+    let merged = ours
+        .merge_concurrent(&theirs, &mut |conflict: &Conflict| {
+            asked.push(conflict.field().to_string());
+            match conflict.field() {
+                "name" => Resolution::Theirs,
+                _ => Resolution::Combine,
+            }
+        })
+        .unwrap();
+
+    assert_eq!(asked, ["name", "balance"]);
+    let obj = applied(&base, &merged);
+    assert_eq!(obj.name, "annie");
+    assert_eq!(obj.balance, 103);
+}
+
+#[test]
+fn patches_of_other_objects_conflict() {
+    let base = account();
+    let other = changed(&base, |a| a.id = 2);
+    let ours = base.get_patch(&changed(&base, |a| a.balance = 0));
+    let theirs = other.get_patch(&changed(&other, |a| a.name = "bob".to_string()));
+
+    let conflicts = ours.merge_concurrent(&theirs, &mut OursWins).unwrap_err();
+
+    assert_eq!(conflicts[0].field(), "id");
+    assert_eq!(conflicts[0].mismatch_type(), MismatchType::ObjectID);
+}
+
+#[test]
+fn merge_concurrent_enum_patches() {
+    let base = Discount::Percent {
+        value: 10,
+        note: "spring".to_string(),
+    };
+    let ours = base.get_patch(&Discount::Percent {
+        value: 15,
+        note: "spring".to_string(),
+    });
+    let theirs = base.get_patch(&Discount::Percent {
+        value: 10,
+        note: "summer".to_string(),
+    });
+
+    let mut obj = base.clone();
+    let merged = ours.merge_concurrent(&theirs, &mut OursWins).unwrap();
+    assert_eq!(merged.apply(&mut obj), Ok(()));
+    assert_eq!(
+        obj,
+        Discount::Percent {
+            value: 15,
+            note: "summer".to_string(),
+        }
+    );
+
+    let fixed = base.get_patch(&Discount::Fixed { cents: 200 });
+    let conflicts = ours.merge_concurrent(&fixed, &mut Sum).unwrap_err();
+    assert_eq!(
+        conflicts,
+        [Conflict::new("", MismatchType::VariantMismatch, false)]
+    );
+    let merged = ours.merge_concurrent(&fixed, &mut TheirsWins).unwrap();
+    assert_eq!(merged, fixed);
+}
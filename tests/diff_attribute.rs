@@ -0,0 +1,93 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Selecting the `Diff` of a field with `#[patch(diff = ...)]`.
+
+use derive_patch::{
+    diff::{CopyDiff, Diff},
+    mismatch::MismatchError,
+    traits::Base,
+    Partial, Patch,
+};
+
+/// A diff that stores only the new value and never conflicts.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverwriteDiff {
+    new_value: String,
+}
+
+impl Diff for OverwriteDiff {
+    type DiffResult = String;
+    type Object = String;
+
+    fn new(_old: &String, new: &String) -> Self {
+        OverwriteDiff {
+            new_value: new.clone(),
+        }
+    }
+
+    fn changes_object(&self, old: &String) -> bool {
+        *old != self.new_value
+    }
+
+    fn contains_change(&self) -> bool {
+        true
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        self.new_value = rhs.new_value.clone();
+        Ok(())
+    }
+
+    fn applies_cleanly(&self, _obj: &String) -> Result<(), MismatchError> {
+        Ok(())
+    }
+
+    fn apply_into(&self, obj: &mut String) -> Result<(), MismatchError> {
+        *obj = self.new_value.clone();
+        Ok(())
+    }
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Article {
+    #[patch(diff = OverwriteDiff)]
+    title: String,
+    #[patch(diff = CopyDiff<u32>)]
+    views: u32,
+}
+
+#[test]
+fn custom_diff() {
+    let mut article = Article {
+        title: "old".to_string(),
+        views: 1,
+    };
+    let mut partial = PartialArticle::new();
+    partial.title = Some("new".to_string());
+    let patch = ArticlePatch::new_with_partial(&article, &partial);
+
+    article.title = "concurrent change".to_string();
+
+    assert_eq!(patch.apply(&mut article), Ok(()));
+    assert_eq!(article.title, "new");
+}
+
+#[test]
+fn explicit_generic_diff() {
+    let mut article = Article {
+        title: "title".to_string(),
+        views: 1,
+    };
+    let mut partial = PartialArticle::new();
+    partial.views = Some(2);
+    let patch = ArticlePatch::new_with_partial(&article, &partial);
+
+    assert_eq!(patch.apply(&mut article), Ok(()));
+    assert_eq!(article.views, 2);
+    assert!(patch.apply(&mut article).is_err());
+}
@@ -0,0 +1,140 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Patches and partials of enums.
+
+use derive_patch::{
+    mismatch::MismatchType,
+    traits::{Base, Patch, PatchableExt},
+    Partial, Patch,
+};
+use std::convert::TryFrom;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Shape {
+    Circle {
+        radius: f64,
+    },
+    Rect {
+        width: u32,
+        height: u32,
+        #[partial(skip, default = "rect".to_string())]
+        #[patch(skip)]
+        label: String,
+    },
+    Empty,
+}
+
+fn rect() -> Shape {
+    Shape::Rect {
+        width: 2,
+        height: 3,
+        label: "rect".to_string(),
+    }
+}
+
+#[test]
+fn enum_patch_same_variant() {
+    let mut obj = rect();
+    let partial = PartialShape::Rect {
+        width: Some(4),
+        height: Some(3),
+    };
+
+    let mut patch = ShapePatch::new_with_partial(&obj, &partial).unwrap();
+
+    assert!(!patch.cleanup());
+    assert_eq!(patch.count(), 1);
+    assert_eq!(ShapePatch::MAX_FIELDS, 2);
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(
+        obj,
+        Shape::Rect {
+            width: 4,
+            height: 3,
+            label: "rect".to_string(),
+        }
+    );
+}
+
+#[test]
+fn enum_patch_switch_variant() {
+    let mut obj = rect();
+    let circle = Shape::Circle { radius: 1.5 };
+
+    let patch = obj.get_patch(&circle);
+    assert!(matches!(patch, ShapePatch::Replace(_)));
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, circle);
+    assert!(patch.apply(&mut obj).is_err());
+}
+
+#[test]
+fn enum_patch_variant_mismatch() {
+    let patch = rect().get_patch(&Shape::Rect {
+        width: 5,
+        height: 3,
+        label: "rect".to_string(),
+    });
+    let mut obj = Shape::Empty;
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    let mismatch = &error.mismatches()[0];
+    assert_eq!(mismatch.mismatch_type(), MismatchType::VariantMismatch);
+    assert_eq!(mismatch.expected, "Rect");
+    assert_eq!(mismatch.received, "Empty");
+    assert_eq!(obj, Shape::Empty);
+}
+
+#[test]
+fn enum_patch_from_incomplete_partial() {
+    let partial = PartialShape::Circle { radius: None };
+
+    assert!(ShapePatch::new_with_partial(&rect(), &partial).is_err());
+    let patch = ShapePatch::new_with_partial(&Shape::Circle { radius: 1.0 }, &partial).unwrap();
+    assert!(patch.is_empty());
+}
+
+#[test]
+fn enum_partial() {
+    let partial = PartialShape::Rect {
+        width: Some(2),
+        height: None,
+    };
+    assert_eq!(PartialShape::MAX_FIELDS, 2);
+    assert_eq!(partial.count(), 1);
+    assert!(!partial.is_complete());
+    assert_eq!(partial.build(), Err(()));
+    assert!(Shape::try_from(partial.clone()).is_err());
+
+    let mut complete = PartialShape::from(rect());
+    assert!(complete.is_partial_equal_existing(&partial));
+    partial.merge_into(&mut complete);
+    assert_eq!(Shape::try_from(complete), Ok(rect()));
+
+    assert_eq!(PartialShape::Empty.build(), Ok(Shape::Empty));
+}
+
+#[test]
+fn enum_partial_apply() {
+    let mut obj = rect();
+    PartialShape::Rect {
+        width: None,
+        height: Some(7),
+    }
+    .apply(&mut obj);
+    assert!(matches!(obj, Shape::Rect { height: 7, .. }));
+
+    // incomplete partials of another variant cannot be applied
+    PartialShape::Circle { radius: None }.apply(&mut obj);
+    assert!(matches!(obj, Shape::Rect { .. }));
+
+    PartialShape::Circle { radius: Some(2.0) }.apply(&mut obj);
+    assert_eq!(obj, Shape::Circle { radius: 2.0 });
+}
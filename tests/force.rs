@@ -0,0 +1,65 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Fields that are always present in the partial with `force`.
+
+use derive_patch::{traits::Base, Partial, Patch};
+use std::convert::TryFrom;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Account {
+    #[partial(force)]
+    #[patch(id)]
+    owner: String,
+    #[partial(force)]
+    limit: u32,
+    balance: i64,
+}
+
+fn account() -> Account {
+    Account {
+        owner: "alice".to_string(),
+        limit: 100,
+        balance: 10,
+    }
+}
+
+#[test]
+fn forced_fields_are_required() {
+    let partial = PartialAccount::new("alice".to_string(), 100);
+
+    assert_eq!(PartialAccount::MAX_FIELDS, 1);
+    assert!(partial.is_empty());
+    assert_eq!(partial.build(), Err(()));
+
+    let error = Account::try_from(partial).unwrap_err();
+    assert!(error.to_string().contains(r#"Account { owner: "alice" }"#));
+}
+
+#[test]
+fn forced_fields_build() {
+    let partial = PartialAccount::with_arguments("alice".to_string(), 100, 10);
+
+    assert!(partial.is_complete());
+    assert_eq!(partial.count(), 1);
+    assert_eq!(Account::try_from(partial), Ok(account()));
+}
+
+#[test]
+fn forced_fields_are_always_diffed() {
+    let mut obj = account();
+    let partial = PartialAccount::new("alice".to_string(), 200);
+
+    let patch = AccountPatch::new_with_partial(&obj, &partial, "alice".to_string());
+    assert_eq!(patch.count(), 1);
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.limit, 200);
+    assert_eq!(obj.balance, 10);
+
+    // unchanged forced fields are left out
+    let patch = AccountPatch::new_with_partial(&obj, &partial, "alice".to_string());
+    assert!(patch.is_empty());
+}
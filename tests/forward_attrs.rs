@@ -0,0 +1,123 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Forwarding `serde`, `doc` and `cfg` attributes of fields to the generated
+//! types.
+
+use derive_patch::{
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Profile {
+    /// The name shown to other users.
+    #[cfg_attr(feature = "serde", serde(rename = "displayName"))]
+    display_name: String,
+    /// Only exists with serde.
+    #[cfg(feature = "serde")]
+    #[partial(force, default)]
+    locale: String,
+    /// Never exists.
+    #[cfg(any())]
+    removed: u32,
+    #[cfg(any())]
+    #[patch(id)]
+    removed_id: u32,
+    #[cfg(any())]
+    #[partial(skip)]
+    #[patch(skip)]
+    removed_skip: u32,
+    age: u32,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Patch, Partial, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[partial(forward_attrs(doc))]
+struct Filtered {
+    #[cfg_attr(feature = "serde", serde(rename = "displayName"))]
+    display_name: String,
+}
+
+fn profile() -> Profile {
+    Profile {
+        display_name: "alice".to_string(),
+        #[cfg(feature = "serde")]
+        locale: "en".to_string(),
+        age: 30,
+    }
+}
+
+#[test]
+fn cfg_removes_the_field_everywhere() {
+    // forced fields are not counted by the partial
+    assert_eq!(PartialProfile::MAX_FIELDS, 2);
+    let max_fields = if cfg!(feature = "serde") { 3 } else { 2 };
+    assert_eq!(ProfilePatch::MAX_FIELDS, max_fields);
+
+    let mut obj = profile();
+    let mut new = profile();
+    new.age = 31;
+    let patch = obj.get_patch(&new);
+
+    assert_eq!(patch.count(), 1);
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, new);
+
+    let partial = PartialProfile::from(new.clone());
+    assert!(partial.is_complete());
+    assert_eq!(partial.build(), Ok(new));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_attrs_are_forwarded() {
+    let mut partial = PartialProfile::new("de".to_string());
+    partial.display_name = Some("bob".to_string());
+
+    let json = serde_json::to_value(&partial).unwrap();
+
+    assert_eq!(json["displayName"], "bob");
+    assert!(json.get("display_name").is_none());
+
+    let patch = profile().get_patch(&partial.build_from(profile()));
+    let json = serde_json::to_value(&patch).unwrap();
+    assert!(json.get("displayName").is_some());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn forward_attrs_filters() {
+    let partial = PartialFiltered::with_arguments("bob".to_string());
+
+    let json = serde_json::to_value(&partial).unwrap();
+
+    assert_eq!(json["display_name"], "bob");
+    // the patch still forwards the default attributes
+    let patch = Filtered {
+        display_name: "alice".to_string(),
+    }
+    .get_patch(&Filtered {
+        display_name: "bob".to_string(),
+    });
+    let json = serde_json::to_value(&patch).unwrap();
+    assert!(json.get("displayName").is_some());
+}
+
+#[cfg(feature = "serde")]
+trait BuildFrom {
+    fn build_from(&self, obj: Profile) -> Profile;
+}
+
+#[cfg(feature = "serde")]
+impl BuildFrom for PartialProfile {
+    /// `obj` with the fields set in this partial.
+    fn build_from(&self, mut obj: Profile) -> Profile {
+        self.apply(&mut obj);
+        obj
+    }
+}
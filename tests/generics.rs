@@ -0,0 +1,187 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Deriving `Patch` and `Partial` for generic structs and enums.
+
+use derive_patch::{
+    diff::Diff,
+    mismatch::MismatchError,
+    patchable::PatchableField,
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Record<K, V> {
+    #[patch(id)]
+    k: K,
+    value: V,
+    #[partial(skip)]
+    #[patch(skip)]
+    cache: Vec<V>,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Pair<T>
+where
+    T: Default,
+{
+    left: T,
+    right: T,
+}
+
+/// Borrowed fields can not be deserialized out of an `Option`, so this struct
+/// only works without serde.
+#[cfg(not(feature = "serde"))]
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct View<'a> {
+    title: &'a str,
+    lines: u32,
+}
+
+/// A diff that only ever raises the value, e.g. for a high score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaxDiff<T>
+where
+    T: Ord + Clone,
+{
+    value: T,
+}
+
+impl<T> Diff for MaxDiff<T>
+where
+    T: Ord + Clone,
+{
+    type DiffResult = T;
+    type Object = T;
+
+    fn new(_old: &T, new: &T) -> Self {
+        MaxDiff { value: new.clone() }
+    }
+
+    fn changes_object(&self, old: &T) -> bool {
+        self.value > *old
+    }
+
+    fn contains_change(&self) -> bool {
+        true
+    }
+
+    fn merge(&mut self, rhs: &Self) -> Result<(), ()> {
+        self.value = self.value.clone().max(rhs.value.clone());
+        Ok(())
+    }
+
+    fn applies_cleanly(&self, _obj: &T) -> Result<(), MismatchError> {
+        Ok(())
+    }
+
+    fn apply_into(&self, obj: &mut T) -> Result<(), MismatchError> {
+        if self.value > *obj {
+            *obj = self.value.clone();
+        }
+        Ok(())
+    }
+}
+
+/// `MaxDiff<T>` needs `T: Ord`, which can not be inferred from the field.
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[patch(bound = "T: Ord + Clone + PatchableField")]
+struct HighScore<T> {
+    #[patch(diff = MaxDiff<T>)]
+    score: T,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Shape<T> {
+    Circle { radius: T },
+    Square { side: T },
+}
+
+#[test]
+fn generic_patch() {
+    let mut obj = Record {
+        k: 1u8,
+        value: "old".to_string(),
+        cache: vec!["old".to_string()],
+    };
+    let mut new = obj.clone();
+    new.value = "new".to_string();
+
+    let patch: RecordPatch<u8, String> = obj.get_patch(&new);
+
+    assert_eq!(patch.count(), 1);
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.value, "new");
+}
+
+#[test]
+fn generic_partial_with_type_param_named_field() {
+    let partial = PartialRecord::with_arguments(3i64, 4.5f64);
+
+    assert!(partial.is_complete());
+    let record = partial.build().unwrap();
+    assert_eq!(record.k, 3);
+    assert_eq!(record.value, 4.5);
+    assert!(record.cache.is_empty());
+}
+
+#[test]
+fn where_clause() {
+    let obj = Pair { left: 1, right: 2 };
+    let new = Pair { left: 1, right: 3 };
+
+    let patch = obj.get_patch(&new);
+
+    assert_eq!(patch.count(), 1);
+    assert!(patch.right.is_some());
+}
+
+#[cfg(not(feature = "serde"))]
+#[test]
+fn lifetime() {
+    let text = String::from("second");
+    let mut obj = View {
+        title: "first",
+        lines: 1,
+    };
+    let new = View {
+        title: &text,
+        lines: 1,
+    };
+
+    let patch = obj.get_patch(&new);
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.title, "second");
+}
+
+#[test]
+fn custom_bound() {
+    let mut obj = HighScore { score: 5u32 };
+    let patch = obj.get_patch(&HighScore { score: 8 });
+
+    // a higher score is kept
+    obj.score = 10;
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.score, 10);
+}
+
+#[test]
+fn generic_enum() {
+    let mut obj = Shape::Circle { radius: 1.0f32 };
+
+    let patch = obj.get_patch(&Shape::Circle { radius: 2.0 });
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, Shape::Circle { radius: 2.0 });
+
+    let patch = obj.get_patch(&Shape::Square { side: 3.0 });
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, Shape::Square { side: 3.0 });
+}
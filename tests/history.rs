@@ -0,0 +1,187 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Undo and redo with `history::UndoStack`.
+
+mod common;
+
+use common::changed;
+use derive_patch::{
+    diff::{TextDiff, Words},
+    history::UndoStack,
+    traits::{Patch, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Note {
+    #[patch(id)]
+    id: u32,
+    title: String,
+    #[patch(diff = TextDiff<Words>)]
+    body: String,
+    pinned: bool,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Mark {
+    Color { rgb: u32 },
+    Hidden,
+}
+
+fn note() -> Note {
+    Note {
+        id: 1,
+        title: "todo".to_string(),
+        body: "buy milk".to_string(),
+        pinned: false,
+    }
+}
+
+/// Changes `obj` with `change`, records the patch and returns the new state.
+fn edit<F>(stack: &mut UndoStack<NotePatch>, obj: &mut Note, change: F) -> Note
+where
+    F: FnOnce(&mut Note),
+{
+    let new = changed(obj, change);
+    assert_eq!(stack.apply(obj.get_patch(&new), obj), Ok(()));
+    new
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut stack = UndoStack::new();
+    let mut obj = note();
+    let first = edit(&mut stack, &mut obj, |n| n.title = "shopping".to_string());
+    let second = edit(&mut stack, &mut obj, |n| n.pinned = true);
+    assert_eq!(obj, second);
+
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(obj, first);
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(obj, note());
+    assert!(!stack.can_undo());
+
+    assert_eq!(stack.redo(&mut obj), Ok(true));
+    assert_eq!(stack.redo(&mut obj), Ok(true));
+    assert_eq!(obj, second);
+    assert_eq!(stack.redo(&mut obj), Ok(false));
+}
+
+#[test]
+fn a_new_patch_clears_redo() {
+    let mut stack = UndoStack::new();
+    let mut obj = note();
+    edit(&mut stack, &mut obj, |n| n.pinned = true);
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert!(stack.can_redo());
+
+    edit(&mut stack, &mut obj, |n| n.title = "other".to_string());
+
+    assert!(!stack.can_redo());
+}
+
+#[test]
+fn groups_are_undone_at_once() {
+    let mut stack = UndoStack::new();
+    let mut obj = note();
+    stack.begin_group();
+    edit(&mut stack, &mut obj, |n| n.title = "shopping".to_string());
+    stack.begin_group();
+    edit(&mut stack, &mut obj, |n| n.body.push_str(" and eggs"));
+    stack.end_group();
+    edit(&mut stack, &mut obj, |n| n.pinned = true);
+    stack.end_group();
+
+    assert_eq!(stack.undo_count(), 1);
+    assert_eq!(stack.steps().next().map(<[_]>::len), Some(3));
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(obj, note());
+}
+
+#[test]
+fn coalescing_merges_edits_of_the_same_field() {
+    let mut stack = UndoStack::new();
+    stack.set_coalescing(true);
+    let mut obj = note();
+    edit(&mut stack, &mut obj, |n| n.body.push_str(" and"));
+    edit(&mut stack, &mut obj, |n| n.body.push_str(" eggs"));
+    let typed = edit(&mut stack, &mut obj, |n| n.body.push_str(" please"));
+    edit(&mut stack, &mut obj, |n| n.pinned = true);
+
+    assert_eq!(stack.undo_count(), 2);
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(obj, typed);
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(obj, note());
+}
+
+#[test]
+fn coalesce_needs_the_same_fields_and_target() {
+    let old = note();
+    let mut title = old.clone();
+    title.title = "a".to_string();
+    let mut both = title.clone();
+    both.pinned = true;
+    let mut patch = old.get_patch(&title);
+
+    assert!(!patch.coalesce(&title.get_patch(&both)));
+
+    let mut retitled = title.clone();
+    retitled.title = "b".to_string();
+    let mut other = title.clone();
+    other.id = 2;
+    assert!(!patch.coalesce(&other.get_patch(&retitled)));
+
+    assert!(patch.coalesce(&title.get_patch(&retitled)));
+    assert_eq!(patch, old.get_patch(&retitled));
+}
+
+#[test]
+fn coalesce_enum_patches() {
+    let red = Mark::Color { rgb: 0xff0000 };
+    let green = Mark::Color { rgb: 0x00ff00 };
+    let blue = Mark::Color { rgb: 0x0000ff };
+    let mut patch = red.get_patch(&green);
+
+    assert!(patch.coalesce(&green.get_patch(&blue)));
+    assert_eq!(patch, red.get_patch(&blue));
+    assert!(!patch.coalesce(&blue.get_patch(&Mark::Hidden)));
+}
+
+#[test]
+fn the_limit_drops_the_oldest_steps() {
+    let mut stack = UndoStack::with_limit(2);
+    let mut obj = note();
+    let first = edit(&mut stack, &mut obj, |n| n.title = "a".to_string());
+    edit(&mut stack, &mut obj, |n| n.title = "b".to_string());
+    edit(&mut stack, &mut obj, |n| n.title = "c".to_string());
+
+    assert_eq!(stack.undo_count(), 2);
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(stack.undo(&mut obj), Ok(true));
+    assert_eq!(stack.undo(&mut obj), Ok(false));
+    assert_eq!(obj, first);
+
+    stack.set_limit(Some(0));
+    assert_eq!(stack.undo_count(), 0);
+}
+
+#[test]
+fn a_conflicting_undo_keeps_the_step() {
+    let mut stack = UndoStack::new();
+    let mut obj = note();
+    edit(&mut stack, &mut obj, |n| n.title = "shopping".to_string());
+
+    // changed without the history
+    obj.title = "changed".to_string();
+    let error = stack.undo(&mut obj).unwrap_err();
+
+    assert_eq!(error.mismatches()[0].name(), "title");
+    assert_eq!(obj.title, "changed");
+    assert_eq!(stack.undo_count(), 1);
+}
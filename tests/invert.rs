@@ -0,0 +1,168 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Undoing patches with `InvertPatch::invert` and `Invert::invert`.
+
+use derive_patch::{
+    diff::{
+        CopyDiff, Diff, Invert, MapDiff, NumericDistanceDiff, SetDiff, TextDiff, VecDiff, Words,
+    },
+    traits::{Base, InvertPatch, Patch, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[patch(extra(revision: u32 = 0))]
+struct Document {
+    #[patch(id)]
+    id: u32,
+    title: String,
+    #[patch(diff = NumericDistanceDiff)]
+    words: i64,
+    #[patch(diff = TextDiff<Words>)]
+    body: String,
+    #[patch(diff = VecDiff<u8>)]
+    pages: Vec<u8>,
+    #[patch(diff = MapDiff<String, u32>)]
+    votes: HashMap<String, u32>,
+    #[patch(diff = SetDiff<u8, BTreeSet<u8>>)]
+    labels: BTreeSet<u8>,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Block {
+    Text { content: String },
+    Image { width: u32, height: u32 },
+}
+
+fn document() -> Document {
+    Document {
+        id: 1,
+        title: "draft".to_string(),
+        words: 120,
+        body: "the quick brown fox jumps".to_string(),
+        pages: vec![1, 2, 3, 4],
+        votes: vec![("alice".to_string(), 1), ("bob".to_string(), 2)]
+            .into_iter()
+            .collect(),
+        labels: vec![1, 2].into_iter().collect(),
+    }
+}
+
+#[test]
+fn copy_diff_swaps_the_values() {
+    let diff: CopyDiff<u32> = Diff::new(&1, &2);
+
+    assert_eq!(diff.invert(), Diff::new(&2, &1));
+    assert_eq!(diff.invert().invert(), diff);
+}
+
+#[test]
+fn numeric_distance_diff_negates_the_difference() {
+    let diff: NumericDistanceDiff<i32> = Diff::new(&10, &15);
+    let mut obj = 15;
+
+    assert_eq!(diff.invert().apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, 10);
+}
+
+#[test]
+fn collection_diffs_restore_the_old_value() {
+    let diff: VecDiff<u8> = Diff::new(&vec![1, 2, 3, 4, 5], &vec![9, 2, 4, 7, 7, 5]);
+    let mut obj = vec![9, 2, 4, 7, 7, 5];
+    assert_eq!(diff.invert().apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, [1, 2, 3, 4, 5]);
+
+    let diff: TextDiff<Words> = Diff::new(&"a b c d".to_string(), &"a x x c".to_string());
+    let mut obj = "a x x c".to_string();
+    assert_eq!(diff.invert().apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, "a b c d");
+}
+
+#[test]
+fn undo_a_patch() {
+    let old = document();
+    let mut new = old.clone();
+    new.title = "final".to_string();
+    new.words += 30;
+    new.body = "the quick red fox jumps high".to_string();
+    new.pages.insert(2, 9);
+    new.pages.pop();
+    new.votes.remove("alice");
+    new.votes.insert("bob".to_string(), 5);
+    new.votes.insert("carol".to_string(), 1);
+    new.labels.insert(3);
+    new.labels.remove(&1);
+    let mut patch = old.get_patch(&new);
+    patch.set_revision(7);
+
+    let mut obj = old.clone();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, new);
+
+    let undo = patch.invert();
+    assert_eq!(undo.revision(), &7);
+    assert_eq!(undo.count(), patch.count());
+    assert_eq!(undo.apply(&mut obj), Ok(()));
+    assert_eq!(obj, old);
+
+    // redo
+    assert_eq!(undo.invert(), patch);
+}
+
+#[test]
+fn inverted_patch_conflicts_like_the_patch() {
+    let old = document();
+    let mut new = old.clone();
+    new.title = "final".to_string();
+    let undo = old.get_patch(&new).invert();
+
+    let mut obj = old.clone();
+    obj.title = "other".to_string();
+    let error = undo.apply(&mut obj).unwrap_err();
+
+    assert_eq!(error.mismatches()[0].name(), "title");
+    assert_eq!(obj.title, "other");
+}
+
+#[test]
+fn undo_an_enum_patch() {
+    let old = Block::Image {
+        width: 2,
+        height: 3,
+    };
+    let resized = Block::Image {
+        width: 4,
+        height: 3,
+    };
+    let replaced = Block::Text {
+        content: "caption".to_string(),
+    };
+
+    for new in &[resized, replaced] {
+        let patch = old.get_patch(new);
+        let mut obj = new.clone();
+
+        assert_eq!(patch.invert().apply(&mut obj), Ok(()));
+        assert_eq!(obj, old);
+    }
+}
+
+#[test]
+fn invert_keeps_the_id() {
+    let old = document();
+    let mut new = old.clone();
+    new.words = 0;
+    let undo = old.get_patch(&new).invert();
+
+    let mut other = new.clone();
+    other.id = 2;
+
+    assert!(undo.is_correct_target(&other).is_err());
+    assert!(undo.is_same_target(&old.get_patch(&new)));
+}
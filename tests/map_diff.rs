@@ -0,0 +1,125 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Storing the changes of a map per key with `MapDiff`.
+
+use derive_patch::{
+    diff::{Diff, MapChange, MapDiff, NumericDistanceDiff},
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Game {
+    #[patch(diff = MapDiff<String, i64, NumericDistanceDiff<i64>>)]
+    scores: HashMap<String, i64>,
+    #[patch(diff = MapDiff<String, String>)]
+    nicknames: HashMap<String, String>,
+    /// Maps are `PatchableField`s, so they work with `CopyDiff` as well.
+    settings: BTreeMap<String, bool>,
+}
+
+fn game() -> Game {
+    let mut scores = HashMap::new();
+    scores.insert("alice".to_string(), 10);
+    scores.insert("bob".to_string(), 20);
+    let mut nicknames = HashMap::new();
+    nicknames.insert("alice".to_string(), "al".to_string());
+    Game {
+        scores,
+        nicknames,
+        settings: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn records_changes_per_key() {
+    let old = game().nicknames;
+    let mut new = old.clone();
+    new.insert("bob".to_string(), "bobby".to_string());
+    new.insert("alice".to_string(), "ally".to_string());
+
+    let diff: MapDiff<String, String> = Diff::new(&old, &new);
+
+    assert_eq!(diff.changes().len(), 2);
+    assert_eq!(
+        diff.change(&"bob".to_string()),
+        Some(&MapChange::Insert("bobby".to_string()))
+    );
+    assert!(matches!(
+        diff.change(&"alice".to_string()),
+        Some(MapChange::Update(_))
+    ));
+}
+
+#[test]
+fn concurrent_edits_to_different_keys() {
+    let base = game();
+
+    let mut first = base.clone();
+    first
+        .nicknames
+        .insert("bob".to_string(), "bobby".to_string());
+    let mut second = base.clone();
+    second.nicknames.remove("alice");
+
+    let first = base.get_patch(&first);
+    let second = base.get_patch(&second);
+
+    let mut obj = base;
+    assert_eq!(first.apply(&mut obj), Ok(()));
+    assert_eq!(second.apply(&mut obj), Ok(()));
+    assert_eq!(obj.nicknames.len(), 1);
+    assert_eq!(obj.nicknames["bob"], "bobby");
+}
+
+#[test]
+fn inner_diff_for_updates() {
+    let base = game();
+
+    let mut first = base.clone();
+    *first.scores.get_mut("alice").unwrap() += 5;
+    let mut second = base.clone();
+    *second.scores.get_mut("alice").unwrap() += 3;
+
+    let first = base.get_patch(&first);
+    let second = base.get_patch(&second);
+
+    // the same key, but `NumericDistanceDiff` never conflicts
+    let mut obj = base;
+    assert_eq!(first.apply(&mut obj), Ok(()));
+    assert_eq!(second.apply(&mut obj), Ok(()));
+    assert_eq!(obj.scores["alice"], 18);
+}
+
+#[test]
+fn conflicting_key_is_reported() {
+    let base = game();
+    let mut new = base.clone();
+    new.nicknames
+        .insert("alice".to_string(), "ally".to_string());
+    let patch = base.get_patch(&new);
+
+    let mut obj = base;
+    obj.nicknames.insert("alice".to_string(), "a".to_string());
+
+    let error = patch.apply(&mut obj).unwrap_err();
+    assert_eq!(error.mismatches()[0].path(), "nicknames.\"alice\"");
+}
+
+#[test]
+fn copy_diff_of_map() {
+    let mut obj = game();
+    let mut new = obj.clone();
+    new.settings.insert("sound".to_string(), true);
+
+    let patch = obj.get_patch(&new);
+
+    assert_eq!(patch.count(), 1);
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, new);
+}
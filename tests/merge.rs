@@ -0,0 +1,265 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Combining patches with `merge_into` and `overwrite_into`, and moving them
+//! to another state of the object with `rebase`.
+
+mod common;
+
+use common::changed;
+use derive_patch::{
+    diff::{BTreeMapDiff, NumericDistanceDiff, SetDiff, VecDiff},
+    mismatch::MismatchType,
+    traits::{Base, Patch, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Stock {
+    count: u32,
+    place: String,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Item {
+    #[patch(id)]
+    sku: u32,
+    name: String,
+    #[patch(diff = NumericDistanceDiff)]
+    price: i64,
+    #[patch(diff = VecDiff<u8>)]
+    sizes: Vec<u8>,
+    #[patch(diff = BTreeMapDiff<String, String>)]
+    specs: BTreeMap<String, String>,
+    #[patch(diff = SetDiff<String>)]
+    tags: HashSet<String>,
+    #[patch(nested)]
+    stock: Stock,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Price {
+    Fixed { cents: u32 },
+    Range { min: u32, max: u32 },
+}
+
+fn item() -> Item {
+    Item {
+        sku: 7,
+        name: "shirt".to_string(),
+        price: 1000,
+        sizes: vec![1, 2, 3],
+        specs: vec![("color".to_string(), "red".to_string())]
+            .into_iter()
+            .collect(),
+        tags: vec!["cotton".to_string()].into_iter().collect(),
+        stock: Stock {
+            count: 5,
+            place: "A1".to_string(),
+        },
+    }
+}
+
+#[test]
+fn merge_into_composes_patches() {
+    let a = item();
+    let b = changed(&a, |i| {
+        i.name = "t-shirt".to_string();
+        i.price += 200;
+        i.sizes.push(4);
+        i.stock.count = 4;
+    });
+    let c = changed(&b, |i| {
+        i.name = "tee".to_string();
+        i.price -= 50;
+        i.sizes.remove(0);
+        i.specs.insert("fit".to_string(), "slim".to_string());
+        i.tags.insert("sale".to_string());
+        i.stock.place = "B2".to_string();
+    });
+    let mut patch = a.get_patch(&b);
+
+    assert_eq!(b.get_patch(&c).merge_into(&mut patch), Ok(()));
+
+    let mut obj = a.clone();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, c);
+    assert_eq!(patch.count(), 6);
+}
+
+#[test]
+fn merge_into_reports_conflicts_per_field() {
+    let a = item();
+    let first = changed(&a, |i| {
+        i.name = "t-shirt".to_string();
+        i.sizes = vec![9];
+    });
+    let other = changed(&a, |i| {
+        i.name = "tee".to_string();
+        i.sizes = vec![8];
+        i.price += 1;
+    });
+    let mut patch = a.get_patch(&first);
+    let before = patch.clone();
+
+    // the second patch does not start where the first one ends
+    let error = a.get_patch(&other).merge_into(&mut patch).unwrap_err();
+
+    let names: Vec<&str> = error.mismatches().iter().map(|m| m.name()).collect();
+    assert_eq!(names, ["name", "sizes"]);
+    assert!(error
+        .mismatches()
+        .iter()
+        .all(|m| m.mismatch_type() == MismatchType::MergeConflict));
+    assert_eq!(patch, before);
+}
+
+#[test]
+fn merge_into_needs_the_same_target() {
+    let a = item();
+    let other = changed(&a, |i| i.sku = 8);
+    let mut patch = a.get_patch(&changed(&a, |i| i.price = 0));
+
+    let error = other
+        .get_patch(&changed(&other, |i| i.price = 1))
+        .merge_into(&mut patch)
+        .unwrap_err();
+
+    assert_eq!(error.mismatches()[0].name(), "sku");
+    assert_eq!(
+        error.mismatches()[0].mismatch_type(),
+        MismatchType::ObjectID
+    );
+}
+
+#[test]
+fn overwrite_into_keeps_the_last_writer() {
+    let a = item();
+    let mut patch = a.get_patch(&changed(&a, |i| {
+        i.name = "t-shirt".to_string();
+        i.price = 900;
+    }));
+
+    let last = a.get_patch(&changed(&a, |i| i.name = "tee".to_string()));
+    assert_eq!(last.overwrite_into(&mut patch), Ok(()));
+
+    let mut obj = a.clone();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.name, "tee");
+    assert_eq!(obj.price, 900);
+}
+
+#[test]
+fn rebase_takes_the_old_values_of_the_object() {
+    let a = item();
+    let mut patch = a.get_patch(&changed(&a, |i| {
+        i.name = "t-shirt".to_string();
+        i.specs.insert("fit".to_string(), "slim".to_string());
+        i.specs.remove("color");
+        i.tags.insert("sale".to_string());
+        i.stock.place = "B2".to_string();
+    }));
+
+    // changed concurrently
+    let mut obj = changed(&a, |i| {
+        i.name = "shirt!".to_string();
+        i.specs.insert("fit".to_string(), "loose".to_string());
+        i.specs.insert("color".to_string(), "blue".to_string());
+        i.tags.insert("sale".to_string());
+        i.stock.place = "C3".to_string();
+    });
+    assert!(patch.check(&obj).is_err());
+
+    assert_eq!(patch.rebase(&obj), Ok(()));
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+
+    assert_eq!(obj.name, "t-shirt");
+    assert_eq!(obj.specs.get("fit").map(String::as_str), Some("slim"));
+    assert_eq!(obj.specs.get("color"), None);
+    assert!(obj.tags.contains("sale"));
+    assert_eq!(obj.stock.place, "B2");
+}
+
+#[test]
+fn rebase_fails_for_diffs_without_new_values() {
+    let a = item();
+    let mut patch = a.get_patch(&changed(&a, |i| {
+        i.name = "t-shirt".to_string();
+        i.sizes.push(4);
+    }));
+    let before = patch.clone();
+
+    let error = patch
+        .rebase(&changed(&a, |i| i.sizes = vec![5]))
+        .unwrap_err();
+
+    assert_eq!(error.mismatches().len(), 1);
+    assert_eq!(error.mismatches()[0].path(), "sizes.3");
+    assert_eq!(patch, before);
+
+    let error = patch.rebase(&changed(&a, |i| i.sku = 1)).unwrap_err();
+    assert_eq!(
+        error.mismatches()[0].mismatch_type(),
+        MismatchType::ObjectID
+    );
+}
+
+#[test]
+fn merge_enum_patches() {
+    let fixed = Price::Fixed { cents: 100 };
+    let cheaper = Price::Fixed { cents: 80 };
+    let range = Price::Range { min: 50, max: 90 };
+    let wider = Price::Range { min: 40, max: 90 };
+
+    let mut patch = fixed.get_patch(&range);
+    assert_eq!(range.get_patch(&wider).merge_into(&mut patch), Ok(()));
+    let mut obj = fixed.clone();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, wider);
+
+    // the value in front of `cheaper` is not known to the replacement
+    let mut patch = fixed.get_patch(&cheaper);
+    let error = cheaper
+        .get_patch(&range)
+        .merge_into(&mut patch)
+        .unwrap_err();
+    assert_eq!(
+        error.mismatches()[0].mismatch_type(),
+        MismatchType::MergeConflict
+    );
+
+    let mut patch = fixed.get_patch(&cheaper);
+    let error = range.get_patch(&wider).merge_into(&mut patch).unwrap_err();
+    assert_eq!(
+        error.mismatches()[0].mismatch_type(),
+        MismatchType::VariantMismatch
+    );
+}
+
+#[test]
+fn overwrite_and_rebase_enum_patches() {
+    let fixed = Price::Fixed { cents: 100 };
+    let range = Price::Range { min: 50, max: 90 };
+    let mut patch = fixed.get_patch(&Price::Fixed { cents: 80 });
+
+    assert_eq!(fixed.get_patch(&range).overwrite_into(&mut patch), Ok(()));
+    assert_eq!(patch, fixed.get_patch(&range));
+
+    let mut obj = Price::Fixed { cents: 120 };
+    assert_eq!(patch.rebase(&obj), Ok(()));
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, range);
+
+    let mut patch = range.get_patch(&Price::Range { min: 60, max: 90 });
+    let error = patch.rebase(&fixed).unwrap_err();
+    assert_eq!(
+        error.mismatches()[0].mismatch_type(),
+        MismatchType::VariantMismatch
+    );
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Renaming the generated types and adding derives and attributes to them.
+
+use derive_patch::{
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::HashSet;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[partial(
+    name = "UserUpdate",
+    derive(Eq, Hash),
+    attr(cfg_attr(feature = "serde", serde(rename_all = "camelCase")))
+)]
+#[patch(name = "UserChange", attr(must_use))]
+struct User {
+    user_name: String,
+    age: u32,
+}
+
+mod model {
+    use derive_patch::{Partial, Patch};
+
+    /// The generated types can not be more or less visible than this one,
+    /// see the docs of `vis`.
+    #[derive(Patch, Partial, Debug, Clone, PartialEq)]
+    #[partial(vis = "pub(crate)")]
+    #[patch(vis = "pub(crate)")]
+    pub(crate) struct Account {
+        pub(crate) balance: i64,
+    }
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[partial(name = "LightUpdate", derive(Eq))]
+#[patch(name = "LightChange")]
+enum Light {
+    Off,
+    On { brightness: u8 },
+}
+
+fn user() -> User {
+    User {
+        user_name: "alice".to_string(),
+        age: 30,
+    }
+}
+
+#[test]
+fn renamed_partial() {
+    let mut update = UserUpdate::new();
+    update.age = Some(31);
+
+    let mut obj = user();
+    update.apply(&mut obj);
+
+    assert_eq!(obj.age, 31);
+}
+
+#[test]
+fn additional_derives() {
+    let mut updates = HashSet::new();
+    updates.insert(UserUpdate::with_arguments(None, 31));
+    updates.insert(UserUpdate::with_arguments(None, 31));
+
+    assert_eq!(updates.len(), 1);
+}
+
+#[test]
+fn renamed_patch_uses_renamed_partial() {
+    let obj = user();
+    let update = UserUpdate::with_arguments("bob".to_string(), None);
+
+    let change: UserChange = UserChange::new_with_partial(&obj, &update);
+
+    assert_eq!(change.count(), 1);
+    assert_eq!(change, obj.get_patch(&update.build_or(&obj)));
+}
+
+#[test]
+fn renamed_enum() {
+    let mut obj = Light::Off;
+    let update = LightUpdate::On {
+        brightness: Some(3),
+    };
+    assert_eq!(update.clone(), update);
+
+    let change: LightChange = LightChange::new_with_partial(&obj, &update).unwrap();
+
+    assert_eq!(change.apply(&mut obj), Ok(()));
+    assert_eq!(obj, Light::On { brightness: 3 });
+}
+
+#[test]
+fn visibility() {
+    let mut account = model::Account { balance: 1 };
+    let partial = model::PartialAccount::with_arguments(2);
+
+    let patch = model::AccountPatch::new_with_partial(&account, &partial);
+
+    assert_eq!(patch.apply(&mut account), Ok(()));
+    assert_eq!(account.balance, 2);
+}
+
+trait BuildOr {
+    fn build_or(&self, obj: &User) -> User;
+}
+
+impl BuildOr for UserUpdate {
+    /// `obj` with the fields set in this update.
+    fn build_or(&self, obj: &User) -> User {
+        let mut obj = obj.clone();
+        self.apply(&mut obj);
+        obj
+    }
+}
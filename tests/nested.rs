@@ -0,0 +1,106 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Patching fields whose type derives `Patch` itself with `nested`.
+
+use derive_patch::{
+    diff::{Diff, NestedPatchDiff},
+    traits::{Base, Patch, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Person {
+    name: String,
+    #[patch(nested)]
+    address: Address,
+}
+
+fn person() -> Person {
+    Person {
+        name: "Alice".to_string(),
+        address: Address {
+            street: "Main Street".to_string(),
+            city: "Springfield".to_string(),
+        },
+    }
+}
+
+fn moved() -> Person {
+    let mut person = person();
+    person.address.city = "Shelbyville".to_string();
+    person
+}
+
+#[test]
+fn nested_patch_only_contains_changed_fields() {
+    let patch = person().get_patch(&moved());
+
+    assert_eq!(patch.count(), 1);
+    let address = patch.address.as_ref().unwrap().patch();
+    assert_eq!(address.count(), 1);
+    assert!(address.street.is_none());
+}
+
+#[test]
+fn nested_apply_descends() {
+    let mut obj = person();
+    let patch = obj.get_patch(&moved());
+
+    // a concurrent change to another field of the nested struct is kept
+    obj.address.street = "Side Street".to_string();
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.address.city, "Shelbyville");
+    assert_eq!(obj.address.street, "Side Street");
+}
+
+#[test]
+fn nested_mismatch_has_dotted_path() {
+    let mut obj = person();
+    let patch = obj.get_patch(&moved());
+    obj.address.city = "Capital City".to_string();
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    assert_eq!(error.mismatches().len(), 1);
+    assert_eq!(error.mismatches()[0].path(), "address.city");
+    assert_eq!(error.mismatches()[0].name(), "address");
+    assert!(error.to_string().contains("field address.city"));
+}
+
+#[test]
+fn nested_from_partial() {
+    let obj = person();
+    let mut partial = PartialPerson::new();
+    partial.address = Some(moved().address);
+
+    let patch = PersonPatch::new_with_partial(&obj, &partial);
+
+    assert_eq!(patch, obj.get_patch(&moved()));
+}
+
+#[test]
+fn nested_cleanup_prunes_empty_patches() {
+    let mut address = AddressPatch::new();
+    address.city = Some(Diff::new(
+        &"Springfield".to_string(),
+        &"Springfield".to_string(),
+    ));
+    let mut patch = PersonPatch::new();
+    patch.address = Some(NestedPatchDiff::from_patch(address));
+
+    assert!(!patch.is_empty());
+    assert!(patch.cleanup());
+    assert!(patch.is_empty());
+}
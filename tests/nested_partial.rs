@@ -0,0 +1,110 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Partials of fields whose type derives `Partial` itself with `nested`.
+
+use derive_patch::{traits::Base, Partial, Patch};
+use std::convert::TryFrom;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Address {
+    street: String,
+    zip: u32,
+}
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct User {
+    name: String,
+    #[partial(nested)]
+    #[patch(nested)]
+    address: Address,
+}
+
+fn user() -> User {
+    User {
+        name: "Alice".to_string(),
+        address: Address {
+            street: "Main Street".to_string(),
+            zip: 12345,
+        },
+    }
+}
+
+fn zip_only(zip: u32) -> PartialUser {
+    let mut address = PartialAddress::new();
+    address.zip = Some(zip);
+    PartialUser::with_arguments(None, address)
+}
+
+#[test]
+fn nested_partial_counts_nested_fields() {
+    assert_eq!(PartialUser::MAX_FIELDS, 3);
+
+    let partial = zip_only(54321);
+    assert_eq!(partial.count(), 1);
+    assert!(!partial.is_empty());
+    assert!(!partial.is_complete());
+    assert!(PartialUser::with_arguments(None, PartialAddress::new()).is_empty());
+}
+
+#[test]
+fn nested_partial_apply() {
+    let mut obj = user();
+
+    zip_only(54321).apply(&mut obj);
+
+    assert_eq!(obj.address.zip, 54321);
+    assert_eq!(obj.address.street, "Main Street");
+}
+
+#[test]
+fn nested_partial_merge_into() {
+    let mut target = PartialUser::from(user());
+
+    zip_only(54321).merge_into(&mut target);
+
+    let mut expected = user();
+    expected.address.zip = 54321;
+    assert_eq!(target.build(), Ok(expected));
+
+    let mut empty = PartialUser::new();
+    zip_only(54321).merge_into(&mut empty);
+    assert_eq!(empty, zip_only(54321));
+}
+
+#[test]
+fn nested_partial_build() {
+    let mut partial = zip_only(12345);
+    partial.name = Some("Alice".to_string());
+    assert_eq!(partial.build(), Err(()));
+    assert!(User::try_from(partial.clone()).is_err());
+
+    partial.address.as_mut().unwrap().street = Some("Main Street".to_string());
+    assert!(partial.is_complete());
+    assert_eq!(partial.build(), Ok(user()));
+    assert_eq!(User::try_from(partial), Ok(user()));
+}
+
+#[test]
+fn nested_partial_is_partial_equal_existing() {
+    let partial = PartialUser::from(user());
+
+    assert!(partial.is_partial_equal_existing(&zip_only(12345)));
+    assert!(!partial.is_partial_equal_existing(&zip_only(54321)));
+}
+
+#[test]
+fn nested_partial_to_patch() {
+    let mut obj = user();
+    let patch = UserPatch::new_with_partial(&obj, &zip_only(54321));
+
+    assert_eq!(patch.count(), 1);
+    obj.address.street = "Side Street".to_string();
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.address.zip, 54321);
+    assert_eq!(obj.address.street, "Side Street");
+}
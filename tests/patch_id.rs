@@ -0,0 +1,73 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Identifying the patched object with `#[patch(id)]` fields.
+
+use derive_patch::{
+    traits::{Base, Patch},
+    Partial, Patch,
+};
+use std::convert::TryFrom;
+
+/// Identified by the composite key `(tenant, number)`.
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Invoice {
+    #[patch(id)]
+    tenant: String,
+    #[patch(id)]
+    number: u32,
+    amount: u64,
+}
+
+fn invoice() -> Invoice {
+    Invoice {
+        tenant: "acme".to_string(),
+        number: 17,
+        amount: 100,
+    }
+}
+
+#[test]
+fn composite_key_patch() {
+    let mut obj = invoice();
+    let mut partial = PartialInvoice::new();
+    partial.amount = Some(200);
+    let patch = InvoicePatch::new_with_partial(&obj, &partial, "acme".to_string(), 17);
+
+    assert_eq!(InvoicePatch::MAX_FIELDS, 1);
+    assert!(patch.is_complete());
+    assert!(patch.is_same_target(&InvoicePatch::new("acme".to_string(), 17)));
+    assert!(!patch.is_same_target(&InvoicePatch::new("acme".to_string(), 18)));
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.amount, 200);
+}
+
+#[test]
+fn composite_key_mismatch() {
+    let mut obj = invoice();
+    let patch = InvoicePatch::new("other".to_string(), 18);
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.starts_with("2 mismatches"));
+    assert!(message.contains("field tenant"));
+    assert!(message.contains("field number"));
+}
+
+#[test]
+fn incomplete_error_contains_ids() {
+    let mut partial = PartialInvoice::new();
+    partial.tenant = Some("acme".to_string());
+    partial.number = Some(17);
+
+    let error = Invoice::try_from(partial).unwrap_err();
+
+    assert!(error
+        .to_string()
+        .contains("failed for Invoice { tenant: Some(\"acme\"), number: Some(17) }"));
+}
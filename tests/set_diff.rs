@@ -0,0 +1,89 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Storing the added and removed elements of a set with `SetDiff`.
+
+use derive_patch::{
+    diff::{BTreeSetDiff, Diff, Lenient, SetDiff},
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Post {
+    #[patch(diff = SetDiff<String>)]
+    tags: HashSet<String>,
+    #[patch(diff = BTreeSetDiff<u32, Lenient>)]
+    readers: BTreeSet<u32>,
+}
+
+fn tags(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn post() -> Post {
+    Post {
+        tags: tags(&["rust", "macro"]),
+        readers: vec![1, 2].into_iter().collect(),
+    }
+}
+
+#[test]
+fn stores_added_and_removed_elements() {
+    let diff: SetDiff<String> = Diff::new(&tags(&["a", "b"]), &tags(&["b", "c"]));
+
+    assert_eq!(diff.added(), ["c".to_string()]);
+    assert_eq!(diff.removed(), ["a".to_string()]);
+}
+
+#[test]
+fn unrelated_elements_do_not_conflict() {
+    let base = post();
+    let mut new = base.clone();
+    new.tags.insert("serde".to_string());
+    let patch = base.get_patch(&new);
+
+    let mut obj = base;
+    obj.tags.remove("macro");
+    obj.tags.insert("derive".to_string());
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.tags, tags(&["rust", "derive", "serde"]));
+}
+
+#[test]
+fn strict_mismatches() {
+    let base = post();
+    let mut new = base.clone();
+    new.tags.insert("serde".to_string());
+    new.tags.remove("macro");
+    let patch = base.get_patch(&new);
+
+    let mut obj = base;
+    obj.tags.insert("serde".to_string());
+    obj.tags.remove("macro");
+
+    let error = patch.apply(&mut obj).unwrap_err();
+    let paths: Vec<&str> = error.mismatches().iter().map(|e| e.path()).collect();
+    assert_eq!(paths, ["tags.\"serde\"", "tags.\"macro\""]);
+}
+
+#[test]
+fn lenient_never_conflicts() {
+    let base = post();
+    let mut new = base.clone();
+    new.readers.insert(3);
+    new.readers.remove(&1);
+    let patch = base.get_patch(&new);
+
+    let mut obj = base;
+    obj.readers.insert(3);
+    obj.readers.remove(&1);
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.readers, vec![2, 3].into_iter().collect());
+}
@@ -0,0 +1,62 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Leaving fields out of patches and partials with `skip`.
+
+use derive_patch::{
+    traits::{Base, Patch},
+    Partial, Patch,
+};
+use std::convert::TryFrom;
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Document {
+    title: String,
+    #[partial(skip)]
+    #[patch(skip)]
+    cache: Vec<u8>,
+    #[partial(skip, default = 1)]
+    revision: u32,
+    #[partial(skip, default)]
+    dirty: bool,
+}
+
+#[test]
+fn skipped_fields_use_defaults() {
+    let partial = PartialDocument::with_arguments("title".to_string());
+    let expected = Document {
+        title: "title".to_string(),
+        cache: Vec::new(),
+        revision: 1,
+        dirty: false,
+    };
+
+    assert_eq!(PartialDocument::MAX_FIELDS, 1);
+    assert_eq!(partial.build(), Ok(expected.clone()));
+    assert_eq!(Document::try_from(partial), Ok(expected));
+}
+
+#[test]
+fn skipped_fields_are_not_patched() {
+    let mut doc = Document {
+        title: "old".to_string(),
+        cache: vec![1, 2, 3],
+        revision: 5,
+        dirty: true,
+    };
+    let patch =
+        DocumentPatch::new_with_partial(&doc, &PartialDocument::with_arguments("new".to_string()));
+
+    assert_eq!(DocumentPatch::MAX_FIELDS, 3);
+    assert_eq!(patch.count(), 1);
+    assert_eq!(patch.apply(&mut doc), Ok(()));
+    assert!(patch.check(&doc).is_err());
+
+    assert_eq!(doc.title, "new");
+    assert_eq!(doc.cache, vec![1, 2, 3]);
+    assert_eq!(doc.revision, 5);
+    assert!(doc.dirty);
+}
@@ -4,185 +4,142 @@
  * For more information, see the LICENSE.md file in this repository.
  */
 
-//#[derive(Partial)]
-
+//! Generates `PartialExample` via `#[derive(Partial)]` and checks that it
+//! behaves like the handwritten template it replaced.
+//!
+//! The code generated for `Example` is pinned in
+//! macro_impl/snapshots/partial_example.rs.
+
+use derive_patch::{traits::Base, Partial};
+use std::convert::TryFrom;
+
+/// The struct the `PartialExample` is generated for.
+#[derive(Partial, Debug, Clone, PartialEq)]
+#[partial(extra(source: String = "template".to_string()))]
 pub struct Example {
     food: f64,
     bard: Option<String>,
 
-    // #[ignorePartial(default => 42)]
     // without an default, we cannot provide a `build`. unless the ignored value type is 'default'
+    #[partial(skip, default = 42)]
     something_special: u32,
 
-    // #[forcePartial(default => 5)] // we cannot provide a Default trait for the partial, unless
-    // default provided, or value type is Default
+    // we cannot provide a Default trait for the partial, unless default provided, or value type
+    // is Default
+    #[partial(force, default = 5)]
     id: u32,
-    /* TODO: partialonly. Data that is present on the Partial but not on the original `Example`
-     * perhabs do this by allowing a derive on the Partial */
 }
 impl Example {
+    /// Uses all fields, so the compiler does not complain about dead code.
     pub fn use_all_vars(&self) -> String {
         format!("{}{}", self.id, self.something_special)
     }
 }
 
-////// generated code starts here
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[derive(PartialEq, Default, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-//TODO: display
-// Default: Default is available when no attributes are forced (`forcePartial`) or the forced values
-// have a default. TODO: Users can customize the Derives
-#[allow(clippy::option_option)]
-pub struct PartialExample {
-    pub food: Option<f64>,
-    pub bard: Option<Option<String>>,
-    id: u32, //forced, so not optional
-}
+/// `None` is ambiguous for the `Option<Option<String>>` argument.
+const NO_BARD: Option<Option<String>> = None;
 
-impl PartialExample {
-    /// Generates a `Partial` by requiring the `forced` values.
-    pub fn new(id: u32) -> PartialExample {
-        // generated if we have forced values.
-        PartialExample {
-            id,
-            food: None,
-            bard: None,
-        }
+fn example() -> Example {
+    Example {
+        food: 2.5,
+        bard: Some("bard".to_string()),
+        something_special: 42,
+        id: 7,
     }
+}
 
-    pub fn with_arguments<FOOD, BARD>(id: u32, food: FOOD, bard: BARD) -> PartialExample
-    where
-        FOOD: std::convert::Into<Option<f64>>,
-        BARD: std::convert::Into<Option<Option<String>>>,
-    {
-        PartialExample {
-            id,
-            food: food.into(),
-            bard: bard.into(),
-        }
-    }
+#[test]
+fn partial_new_is_empty() {
+    let partial = PartialExample::new(7);
 
-    /// add all attributes set here in to the given Partial
-    ///
-    /// If an attribute in here is already set in `obj`, then it gets
-    /// overwritten
-    pub fn merge_into(&self, obj: &mut PartialExample) {
-        //for each attribute
-        if let Some(food) = &self.food {
-            obj.food = Some(*food);
-        }
-        if let Some(bard) = &self.bard {
-            obj.bard = Some(bard.clone());
-        }
-    }
+    assert!(partial.is_empty());
+    assert!(!partial.is_complete());
+    assert_eq!(partial.count(), 0);
+    assert_eq!(partial.id, 7);
+    assert_eq!(PartialExample::MAX_FIELDS, 2);
+}
 
-    /// check all attributes that are present on BOTH objects, if they are
-    /// equal. If there are no attributes present in both, will return true.
-    pub fn is_partial_equal_existing(&self, obj: &PartialExample) -> bool {
-        //for each attribute
-        if let Some(food) = &self.food {
-            if let Some(otherfood) = &obj.food {
-                if (*food - *otherfood).abs() > std::f64::EPSILON {
-                    // special comparison for f32 and f64
-                    return false;
-                }
-            }
-        }
-
-        if let Some(bard) = &self.bard {
-            if let Some(otherbard) = &obj.bard {
-                if *bard != *otherbard {
-                    return false;
-                }
-            }
-        }
-
-        true
-    }
+#[test]
+fn partial_default_uses_forced_default() {
+    let partial = PartialExample::default();
 
-    pub fn build(&self) -> Result<Example, ()> {
-        if let Some(food) = &self.food {
-            if let Some(bard) = &self.bard {
-                return Ok(Example {
-                    food: *food,
-                    bard: bard.clone(),
-                    id: self.id,
-                    something_special: 42,
-                });
-            }
-        }
-        Err(())
-    }
+    assert!(partial.is_empty());
+    assert_eq!(partial, PartialExample::new(5));
 }
 
-impl Base<()> for PartialExample {
-    type Target = Example;
+#[test]
+fn partial_with_arguments() {
+    let partial = PartialExample::with_arguments(7, 1.5, NO_BARD);
 
-    const MAX_FIELDS: usize = 2;
+    assert!(!partial.is_empty());
+    assert!(!partial.is_complete());
+    assert_eq!(partial.count(), 1);
+    assert_eq!(partial.food, Some(1.5));
+    assert_eq!(partial.bard, None);
+    assert_eq!(partial.id, 7);
+}
 
-    fn is_complete(&self) -> bool {
-        //for each attribute
-        self.food.is_some() && self.bard.is_some()
-    }
+#[test]
+fn partial_merge_into() {
+    let partial = PartialExample::with_arguments(8, 1.5, NO_BARD);
+    let mut target = PartialExample::with_arguments(7, None, Some(None));
 
-    fn is_empty(&self) -> bool {
-        self.food.is_none() && self.bard.is_none()
-    }
+    partial.merge_into(&mut target);
 
-    /// Counts the number of set attributes.
-    ///
-    /// Forced values do not count, as they are always present
-    fn count(&self) -> u32 {
-        let mut count = 0;
-        //for each attribute
-        if self.food.is_some() {
-            count += 1;
-        }
-
-        if self.bard.is_some() {
-            count += 1;
-        }
-
-        count
-    }
+    assert_eq!(target, PartialExample::with_arguments(8, 1.5, Some(None)));
+}
 
-    fn apply(&self, obj: &mut Self::Target) {
-        //for each attribute
-        if let Some(food) = &self.food {
-            obj.food = *food;
-        }
-        if let Some(bard) = &self.bard {
-            // clone because 'Option' does not implement Copy
-            obj.bard = bard.clone();
-        }
-    }
+#[test]
+fn partial_is_partial_equal_existing() {
+    let partial = PartialExample::with_arguments(8, 1.5, NO_BARD);
+
+    assert!(partial.is_partial_equal_existing(&PartialExample::new(8)));
+    assert!(partial.is_partial_equal_existing(&PartialExample::with_arguments(8, 1.5, Some(None))));
+    assert!(!partial.is_partial_equal_existing(&PartialExample::with_arguments(8, 2.5, NO_BARD)));
+    // forced fields are always present, so they are always compared
+    assert!(!partial.is_partial_equal_existing(&PartialExample::new(9)));
 }
 
-use derive_patch::{mismatch::IncompleteError, traits::Base};
-
-impl std::convert::TryFrom<PartialExample> for Example {
-    type Error = IncompleteError<PartialExample>;
-
-    // This function requires all `ignorePartial` values to have a default.
-    fn try_from(value: PartialExample) -> Result<Self, Self::Error> {
-        if value.is_complete() {
-            Ok(Example {
-                id: value.id,
-                bard: value.bard.unwrap(),
-                food: value.food.unwrap(),
-
-                something_special: 42,
-            })
-        } else {
-            Err(Self::Error::new(
-                "try_from",
-                value.id.to_string(), /* todo: combine
-                                       * all ids! */
-                value,
-            ))
-        }
-    }
+#[test]
+fn partial_build() {
+    let mut partial = PartialExample::with_arguments(7, 2.5, NO_BARD);
+    assert_eq!(partial.build(), Err(()));
+
+    partial.bard = Some(Some("bard".to_string()));
+    assert!(partial.is_complete());
+    assert_eq!(partial.build(), Ok(example()));
+}
+
+#[test]
+fn partial_apply() {
+    let partial = PartialExample::with_arguments(9, None, Some(None));
+    let mut obj = example();
+
+    partial.apply(&mut obj);
+
+    assert_eq!(obj.food, 2.5);
+    assert_eq!(obj.bard, None);
+    assert_eq!(obj.something_special, 42);
+    assert_eq!(obj.id, 9);
+}
+
+#[test]
+fn partial_try_from() {
+    let partial = PartialExample::with_arguments(7, 2.5, Some("bard".to_string()));
+    assert_eq!(Example::try_from(partial), Ok(example()));
+
+    let incomplete = PartialExample::with_arguments(7, 2.5, NO_BARD);
+    assert!(Example::try_from(incomplete).is_err());
+}
+
+#[test]
+fn partial_extra_fields() {
+    let mut partial = PartialExample::with_arguments(7, 2.5, Some("bard".to_string()));
+    assert_eq!(partial.source(), "template");
+    assert_eq!(PartialExample::default().source(), "template");
+
+    partial.set_source("import".to_string());
+    assert_eq!(partial.source(), "import");
+    assert_eq!(partial.count(), 2);
+    assert_eq!(partial.build(), Ok(example()));
 }
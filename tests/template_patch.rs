@@ -4,241 +4,218 @@
  * For more information, see the LICENSE.md file in this repository.
  */
 
+//! Generates `ExamplePatch` via `#[derive(Patch)]` and checks that it behaves
+//! like the handwritten template it replaced.
+//!
+//! The code generated for `Example` is pinned in
+//! macro_impl/snapshots/patch_example.rs.
+
 #![deny(missing_docs)]
 
-mod template_partial;
 use derive_patch::{
-    diff::{CopyDiff, Diff, NumericDistanceDiff},
-    mismatch::{MismatchError, MismatchType, MultipleMismatchError},
-    traits::{Base, Patch, PatchableExt},
+    diff::NumericDistanceDiff,
+    traits::{Base, Patch},
+    Partial, Patch,
 };
-use template_partial::PartialExample;
 
-#[derive(Debug)]
+/// The struct the `ExamplePatch` is generated for.
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[patch(extra(timestamp: u64, patch_id: u32 = 1))]
 pub struct Example {
-    // #[diff = NumericDistanceDiff]
+    #[patch(diff = NumericDistanceDiff)]
     food: f64,
     bard: Option<String>,
 
-    // #[nonPatchable]
+    #[patch(skip)]
     something_special: u32,
 
-    // #[patchid]
+    #[patch(id)]
     id: String,
-    /*todo: allow for fields that are present in the patch but not in the original `Example``
-     * e.g. for Timestamp, patch_id */
 }
 impl Example {
+    /// Uses all fields, so the compiler does not complain about dead code.
     pub fn use_all_vars(&self) -> String {
         format!("{}", self.something_special)
     }
 }
 
-// Here starts the autogenerated parts
+fn example() -> Example {
+    Example {
+        food: 2.5,
+        bard: Some("bard".to_string()),
+        something_special: 42,
+        id: "id".to_string(),
+    }
+}
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+fn changes() -> PartialExample {
+    let mut partial = PartialExample::new();
+    partial.food = Some(3.5);
+    partial.bard = Some(None);
+    partial
+}
 
-impl PatchableExt for Example {}
+#[test]
+fn patch_new_is_empty() {
+    let patch = ExamplePatch::new("id".to_string());
 
-#[derive(PartialEq, Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct ExamplePatch {
-    food: Option<NumericDistanceDiff<f64>>,
-    bard: Option<CopyDiff<Option<String>>>,
+    assert!(patch.is_empty());
+    assert!(!patch.is_complete());
+    assert_eq!(patch.count(), 0);
+    assert_eq!(ExamplePatch::MAX_FIELDS, 2);
+}
 
-    id: String,
+#[test]
+fn patch_new_with_partial() {
+    let obj = example();
+    let mut partial = changes();
+    // same value as `obj`, so it should not end up in the patch
+    partial.something_special = Some(42);
+
+    let patch = ExamplePatch::new_with_partial(&obj, &partial, "id".to_string());
+
+    assert!(!patch.is_empty());
+    assert_eq!(patch.count(), 2);
+    assert_eq!(
+        patch,
+        ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string())
+    );
 }
 
-impl ExamplePatch {
-    pub fn new(id: String) -> ExamplePatch {
-        ExamplePatch {
-            id,
-            food: None,
-            bard: None,
-        }
-    }
+#[test]
+fn patch_apply() {
+    let mut obj = example();
+    let patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
 
-    pub fn new_with_partial(
-        obj: &Example,
-        partial: &PartialExample,
-        id: String, /* all patchid attributes */
-    ) -> ExamplePatch {
-        let food = match partial.food {
-            Some(food) => {
-                let diff = NumericDistanceDiff::new(&obj.food, &food);
-                if diff.contains_change() {
-                    Some(diff)
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
-
-        let bard = match &partial.bard {
-            Some(bard) => {
-                let diff = CopyDiff::new(&obj.bard, &bard);
-
-                if diff.contains_change() {
-                    Some(diff)
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
-
-        ExamplePatch { id, food, bard }
-    }
+    assert_eq!(patch.check(&obj), Ok(()));
+    assert_eq!(patch.apply(&mut obj), Ok(()));
 
-    /// internal apply function. Responsible for actually assigning the values.
-    ///
-    /// This function performs no checks, it's the responsibility of the caller
-    /// to perform checks.
-    ///
-    /// Used by `apply` and `force_apply`
-    fn _apply(&self, obj: &mut Example) {
-        // apply the patch
-        if let Some(food) = &self.food {
-            let _ = food.apply_into(&mut obj.food);
-        }
-        if let Some(bard) = &self.bard {
-            let _ = bard.apply_into(&mut obj.bard);
-        }
-    }
+    assert_eq!(obj.food, 3.5);
+    assert_eq!(obj.bard, None);
+    assert_eq!(obj.something_special, 42);
+    assert_eq!(obj.id, "id");
 }
-impl Base<Result<(), MultipleMismatchError>> for ExamplePatch {
-    type Target = Example;
 
-    /// Available fields
-    /// - `food`
-    /// - `bard`
-    const MAX_FIELDS: usize = 2;
+#[test]
+fn patch_apply_twice_fails() {
+    let mut obj = example();
+    let patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+    patch.apply(&mut obj).unwrap();
+    let patched = obj.clone();
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    assert!(error.has_errors());
+    let message = error.to_string();
+    // `NumericDistanceDiff` always applies, only the `CopyDiff` fails
+    assert!(message.starts_with("1 mismatches"));
+    assert!(message.contains("field bard"));
+    assert_eq!(obj, patched);
+}
 
-    /// returns true if ALL fields have an associated diff.
-    fn is_complete(&self) -> bool {
-        self.food.is_some() && self.bard.is_some()
-    }
+#[test]
+fn patch_numeric_distance_diff() {
+    let mut obj = example();
+    let mut partial = PartialExample::new();
+    partial.food = Some(3.5);
+    let patch = ExamplePatch::new_with_partial(&obj, &partial, "id".to_string());
 
-    fn is_empty(&self) -> bool {
-        self.food.is_none() && self.bard.is_none()
-    }
+    patch.apply(&mut obj).unwrap();
+    patch.apply(&mut obj).unwrap();
 
-    fn count(&self) -> u32 {
-        let mut count = 0;
-        //for each attribute
-        if self.food.is_some() {
-            count += 1;
-        }
+    assert_eq!(obj.food, 4.5);
+}
 
-        if self.bard.is_some() {
-            count += 1;
-        }
+#[test]
+fn patch_cleanup() {
+    let obj = example();
+    let mut patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
 
-        count
-    }
+    assert!(!patch.cleanup());
+    assert_eq!(patch.count(), 2);
+}
 
-    /// applies the patch to the given object
-    ///
-    /// makes sure the `id` of `Example` matches the `id` field of this Patch.
-    /// Returns an Error if that is not the case.
-    ///
-    /// makes sure that all old_values of the patches field are the current
-    /// value of the given example. Returns an Error otherwise.
-    ///
-    /// This function does not change `obj` if an Error occurs.
-    fn apply(&self, obj: &mut Example) -> Result<(), MultipleMismatchError> {
-        if let Err(e) = self.is_correct_target(obj) {
-            return Err(e);
-        }
-
-        if let Err(e) = self.can_apply_cleanly(obj) {
-            return Err(e);
-        }
-
-        self._apply(obj);
-
-        Ok(())
-    }
+#[test]
+fn patch_merge_into() {
+    let mut obj = example();
+    let mut patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+    let mut partial = PartialExample::new();
+    partial.food = Some(4.0);
+    partial.bard = Some(Some("new".to_string()));
+    let mut changed = obj.clone();
+    patch.apply(&mut changed).unwrap();
+    let next = ExamplePatch::new_with_partial(&changed, &partial, "id".to_string());
+
+    assert_eq!(next.merge_into(&mut patch), Ok(()));
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+
+    assert_eq!(obj.food, 4.0);
+    assert_eq!(obj.bard, Some("new".to_string()));
 }
 
-impl Patch for ExamplePatch {
-    ///todo: adds changes from this patch ontop of the given patch.
-    //fn merge_into(&self, obj: &mut ExamplePatch) -> Result<(), ()> {
-    //    Ok(()) //TODO:
-    //}
+#[test]
+fn patch_overwrite_into() {
+    let obj = example();
+    let mut patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+    let mut partial = PartialExample::new();
+    partial.bard = Some(Some("new".to_string()));
+    let last = ExamplePatch::new_with_partial(&obj, &partial, "id".to_string());
 
-    ///todo: overwrites the changes in the given patch
-    //fn overwrite_into() {}
+    assert_eq!(last.overwrite_into(&mut patch), Ok(()));
 
-    //todo: keeps all the new values, but sets the old values from the current obj
-    //fn rebase(&mut self, obj: &Example) {}
+    assert_eq!(patch.count(), 2);
+    assert_eq!(patch.bard, last.bard);
+}
 
-    fn cleanup(&mut self) -> bool {
-        let mut changed = false;
-        if let Some(food) = &self.food {
-            if !food.contains_change() {
-                self.food = None;
-                changed = true;
-            }
-        }
+#[test]
+fn patch_rebase() {
+    let mut obj = example();
+    let mut patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+    obj.bard = Some("concurrent change".to_string());
+    assert!(patch.check(&obj).is_err());
 
-        if let Some(bard) = &self.bard {
-            if !bard.contains_change() {
-                self.bard = None;
-                changed = true;
-            }
-        }
+    assert_eq!(patch.rebase(&obj), Ok(()));
+    assert_eq!(patch.apply(&mut obj), Ok(()));
 
-        changed
-    }
+    assert_eq!(obj.bard, None);
+}
 
-    fn is_correct_target(&self, obj: &Self::Target) -> Result<(), MultipleMismatchError> {
-        let mut error = MultipleMismatchError::new();
-
-        //check all id types
-        if self.id != obj.id {
-            error.add_error(MismatchError::new(
-                "id",
-                format!("{:?}", obj.id),
-                format!("{:?}", self.id),
-                MismatchType::ObjectID,
-            ));
-        }
-
-        if error.is_error_free() {
-            Ok(())
-        } else {
-            Err(error)
-        }
-    }
+#[test]
+fn patch_target() {
+    let obj = example();
+    let patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
 
-    fn can_apply_cleanly(&self, obj: &Self::Target) -> Result<(), MultipleMismatchError> {
-        let mut error = MultipleMismatchError::new();
-
-        //check all old values match
-        if let Some(food) = &self.food {
-            if let Err(e) = food.applies_cleanly(&obj.food) {
-                error.add_error(e);
-            }
-        }
-        if let Some(bard) = &self.bard {
-            if let Err(e) = bard.applies_cleanly(&obj.bard) {
-                error.add_error(e);
-            }
-        }
-
-        if error.is_error_free() {
-            Ok(())
-        } else {
-            Err(error)
-        }
-    }
+    assert_eq!(patch.is_correct_target(&obj), Ok(()));
+    assert!(patch.is_same_target(&ExamplePatch::new("id".to_string())));
+    assert!(!patch.is_same_target(&ExamplePatch::new("other".to_string())));
+}
 
-    //check that all id fields are the same
-    fn is_same_target(&self, other: &Self) -> bool {
-        //for all id fields
-        self.id == other.id
-    }
+#[test]
+fn patch_wrong_target() {
+    let mut obj = example();
+    let patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+    obj.id = "other".to_string();
+    let unchanged = obj.clone();
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    assert!(error.to_string().contains("field id"));
+    assert_eq!(patch.check(&obj), Err(error));
+    assert_eq!(obj, unchanged);
+}
+
+#[test]
+fn patch_extra_fields() {
+    let mut obj = example();
+    let mut patch = ExamplePatch::new_with_partial(&obj, &changes(), "id".to_string());
+
+    assert_eq!(*patch.timestamp(), 0);
+    assert_eq!(*patch.patch_id(), 1);
+
+    patch.set_timestamp(1_588_000_000);
+    assert_eq!(*patch.timestamp(), 1_588_000_000);
+    assert_eq!(patch.count(), 2);
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.food, 3.5);
 }
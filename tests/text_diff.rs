@@ -0,0 +1,127 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Storing the changes of a text as hunks with `TextDiff`.
+
+use derive_patch::{
+    diff::{Chars, Diff, TextDiff, Words},
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Article {
+    title: String,
+    #[patch(diff = TextDiff)]
+    body: String,
+}
+
+const BODY: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+
+fn article(body: &str) -> Article {
+    Article {
+        title: "numbers".to_string(),
+        body: body.to_string(),
+    }
+}
+
+#[test]
+fn stores_hunks_with_context() {
+    let diff: TextDiff = Diff::new(&BODY.to_string(), &BODY.replace("five", "5"));
+
+    assert_eq!(diff.hunks().len(), 1);
+    let hunk = &diff.hunks()[0];
+    assert_eq!(hunk.index(), 4);
+    assert_eq!(hunk.before(), ["two\n", "three\n", "four\n"]);
+    assert_eq!(hunk.removed(), ["five\n"]);
+    assert_eq!(hunk.inserted(), ["5\n"]);
+    assert_eq!(hunk.after(), ["six\n", "seven\n", "eight\n"]);
+}
+
+#[test]
+fn unrelated_changes_do_not_conflict() {
+    let mut obj = article(BODY);
+    let patch = obj.get_patch(&article(&BODY.replace("five", "5")));
+
+    obj.body = obj.body.replace("ten", "10");
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.body, BODY.replace("five", "5").replace("ten", "10"));
+}
+
+#[test]
+fn changed_context_conflicts() {
+    let mut obj = article(BODY);
+    let patch = obj.get_patch(&article(&BODY.replace("five", "5")));
+
+    obj.body = obj.body.replace("four", "4");
+
+    let error = patch.apply(&mut obj).unwrap_err();
+    assert_eq!(error.mismatches()[0].path(), "body.4");
+}
+
+#[test]
+fn insertions_in_front_of_a_hunk_do_not_conflict() {
+    let mut obj = article(BODY);
+    let patch = obj.get_patch(&article(&BODY.replace("five", "5")));
+
+    obj.body = format!("minus one\nzero\n{}", obj.body);
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(
+        obj.body,
+        format!("minus one\nzero\n{}", BODY.replace("five", "5"))
+    );
+}
+
+#[test]
+fn fuzzy_apply_limits_the_search() {
+    let diff: TextDiff = Diff::new(&BODY.to_string(), &BODY.replace("five", "5"));
+    let mut obj = format!("zero\n{}", BODY);
+
+    assert!(diff.applies_fuzzy(&obj, 0).is_err());
+    assert!(diff.applies_fuzzy(&obj, 1).is_ok());
+    assert_eq!(diff.apply_fuzzy(&mut obj, 1), Ok(()));
+    assert_eq!(obj, format!("zero\n{}", BODY.replace("five", "5")));
+
+    let mut moved_too_far = format!("a\nb\n{}", BODY);
+    assert!(diff.apply_fuzzy(&mut moved_too_far, 1).is_err());
+    assert_eq!(diff.apply_into(&mut moved_too_far), Ok(()));
+}
+
+#[test]
+fn word_and_char_granularity() {
+    let old = "the quick brown fox".to_string();
+    let new = "the slow brown fox".to_string();
+
+    let words: TextDiff<Words> = Diff::new(&old, &new);
+    assert_eq!(words.hunks()[0].removed(), ["quick"]);
+    assert_eq!(words.hunks()[0].inserted(), ["slow"]);
+
+    let chars: TextDiff<Chars> = Diff::new(&old, &new);
+    let mut obj = old.clone();
+    assert_eq!(chars.apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, new);
+}
+
+#[test]
+fn merge_sequential_diffs() {
+    let first = BODY.to_string();
+    let second = BODY.replace("two", "2");
+    let third = second.replace("three", "3").replace("nine", "9");
+
+    let mut diff: TextDiff = Diff::new(&first, &second);
+    assert_eq!(diff.merge(&Diff::new(&second, &third)), Ok(()));
+
+    assert_eq!(diff.hunks().len(), 2);
+    assert_eq!(diff.hunks()[0].removed(), ["two\n", "three\n"]);
+    assert_eq!(diff.hunks()[0].before(), ["one\n"]);
+    assert_eq!(diff.hunks()[0].after(), ["four\n", "five\n", "six\n"]);
+
+    let mut obj = first;
+    assert_eq!(diff.apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, third);
+}
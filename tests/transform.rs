@@ -0,0 +1,135 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Operational transformation of concurrent diffs with `Transform`.
+
+use derive_patch::diff::{
+    Chars, CopyDiff, Diff, NumericDistanceDiff, Priority, TextDiff, Transform, VecDiff, Words,
+};
+use proptest::prelude::*;
+use std::fmt::Debug;
+
+/// Applies `diff` to a copy of `obj`.
+fn applied<D: Diff>(diff: &D, obj: &D::Object) -> D::Object
+where
+    D::Object: Clone + Debug,
+{
+    let mut obj = obj.clone();
+    assert_eq!(diff.apply_into(&mut obj), Ok(()));
+    obj
+}
+
+/// Applies the diffs from `base` to `a` and to `b` in both orders, with the
+/// second one transformed against the first one.
+///
+/// Returns both results, which have to be the same (TP1).
+fn both_orders<D>(base: &D::Object, a: &D::Object, b: &D::Object) -> (D::Object, D::Object)
+where
+    D: Transform,
+    D::Object: Clone + Debug,
+{
+    let ours = D::new(base, a);
+    let theirs = D::new(base, b);
+    let first = applied(
+        &theirs.transform(&ours, Priority::Theirs),
+        &applied(&ours, base),
+    );
+    let second = applied(
+        &ours.transform(&theirs, Priority::Ours),
+        &applied(&theirs, base),
+    );
+    (first, second)
+}
+
+fn vec_of(text: &str) -> Vec<char> {
+    text.chars().collect()
+}
+
+#[test]
+fn numbers_add_up() {
+    assert_eq!(
+        both_orders::<NumericDistanceDiff<i32>>(&10, &15, &7),
+        (12, 12)
+    );
+}
+
+#[test]
+fn the_priority_breaks_a_tie() {
+    let base = "old".to_string();
+    let (a, b) = ("ours".to_string(), "theirs".to_string());
+
+    let (first, second) = both_orders::<CopyDiff<String>>(&base, &a, &b);
+    assert_eq!((first.as_str(), second.as_str()), ("ours", "ours"));
+}
+
+#[test]
+fn edits_at_other_places_are_moved() {
+    let (first, second) =
+        both_orders::<VecDiff<char>>(&vec_of("abcdef"), &vec_of("Xabcdef"), &vec_of("abcdeYf"));
+
+    assert_eq!(first, vec_of("XabcdeYf"));
+    assert_eq!(second, first);
+}
+
+#[test]
+fn overlapping_edits_keep_the_ones_with_priority() {
+    let (first, second) =
+        both_orders::<VecDiff<char>>(&vec_of("abcdef"), &vec_of("aXXf"), &vec_of("abYYYf"));
+
+    assert_eq!(first, vec_of("aXXf"));
+    assert_eq!(second, first);
+}
+
+#[test]
+fn insertions_at_the_same_index_are_both_kept() {
+    let (first, second) =
+        both_orders::<VecDiff<char>>(&vec_of("ab"), &vec_of("aXb"), &vec_of("aYb"));
+
+    assert_eq!(first, vec_of("aXYb"));
+    assert_eq!(second, first);
+}
+
+#[test]
+fn concurrent_text_edits() {
+    let base = "the quick fox jumps".to_string();
+    let a = "the quick brown fox jumps".to_string();
+    let b = "the quick fox jumps high".to_string();
+
+    let (first, second) = both_orders::<TextDiff<Words>>(&base, &a, &b);
+
+    assert_eq!(first, "the quick brown fox jumps high");
+    assert_eq!(second, first);
+}
+
+proptest! {
+    #[test]
+    fn tp1_numbers(base in -100i32..100, a in -100i32..100, b in -100i32..100) {
+        let (first, second) = both_orders::<NumericDistanceDiff<i32>>(&base, &a, &b);
+        prop_assert_eq!(first, second);
+    }
+
+    #[test]
+    fn tp1_copy(base in 0u8..4, a in 0u8..4, b in 0u8..4) {
+        let (first, second) = both_orders::<CopyDiff<u8>>(&base, &a, &b);
+        prop_assert_eq!(first, second);
+    }
+
+    #[test]
+    fn tp1_vec(
+        base in prop::collection::vec(0u8..4, 0..10),
+        a in prop::collection::vec(0u8..4, 0..10),
+        b in prop::collection::vec(0u8..4, 0..10),
+    ) {
+        let (first, second) = both_orders::<VecDiff<u8>>(&base, &a, &b);
+        prop_assert_eq!(first, second);
+    }
+
+    #[test]
+    fn tp1_text(base in "[ab ]{0,10}", a in "[ab ]{0,10}", b in "[ab ]{0,10}") {
+        let (first, second) = both_orders::<TextDiff<Chars>>(&base, &a, &b);
+        prop_assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Deriving `Patch` and `Partial` for tuple structs and newtypes.
+
+use derive_patch::{
+    diff::{CopyDiff, Diff},
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Meters(f64);
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Pair(u32, String);
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Tagged(
+    #[patch(id)] u32,
+    #[partial(skip)]
+    #[patch(skip)]
+    Vec<u8>,
+    String,
+);
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Route {
+    #[patch(diff = MetersPatch)]
+    length: Meters,
+}
+
+#[test]
+fn positional_partial() {
+    let partial = PartialPair::with_arguments(1, None);
+
+    assert_eq!(partial.0, Some(1));
+    assert_eq!(partial.1, None);
+    assert_eq!(PartialPair::MAX_FIELDS, 2);
+    assert!(partial.build().is_err());
+
+    let pair = PartialPair::with_arguments(1, "one".to_string())
+        .build()
+        .unwrap();
+    assert_eq!(pair, Pair(1, "one".to_string()));
+}
+
+#[test]
+fn positional_patch() {
+    let mut obj = Pair(1, "one".to_string());
+    let patch = obj.get_patch(&Pair(1, "two".to_string()));
+
+    assert_eq!(patch.count(), 1);
+    assert!(patch.0.is_none());
+    assert!(patch.1.is_some());
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj.1, "two");
+}
+
+#[test]
+fn positional_mismatch_names() {
+    let mut obj = Pair(1, "one".to_string());
+    let patch = obj.get_patch(&Pair(2, "one".to_string()));
+    obj.0 = 3;
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    assert_eq!(error.mismatches()[0].name(), "0");
+}
+
+#[test]
+fn skipped_fields_are_left_out() {
+    let mut partial = PartialTagged::new();
+    partial.0 = Some(7);
+    partial.1 = Some("seven".to_string());
+
+    let tagged = partial.build().unwrap();
+    assert_eq!(tagged, Tagged(7, Vec::new(), "seven".to_string()));
+
+    let mut obj = Tagged(7, vec![1], "old".to_string());
+    let patch = TaggedPatch::new_with_partial(&obj, &partial, 7);
+    assert_eq!(patch.0, 7);
+    assert!(patch.1.is_some());
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, Tagged(7, vec![1], "seven".to_string()));
+}
+
+#[test]
+fn newtype_patch_is_a_diff() {
+    let diff: MetersPatch = Diff::new(&Meters(1.0), &Meters(2.0));
+
+    assert!(diff.contains_change());
+    assert_eq!(diff, MetersPatch::from(CopyDiff::new(&1.0, &2.0)));
+
+    let mut route = Route {
+        length: Meters(1.0),
+    };
+    let patch = route.get_patch(&Route {
+        length: Meters(2.0),
+    });
+    assert_eq!(patch.apply(&mut route), Ok(()));
+    assert_eq!(route.length, Meters(2.0));
+}
+
+#[test]
+fn newtype_mismatch_is_named_after_outer_field() {
+    let mut route = Route {
+        length: Meters(1.0),
+    };
+    let patch = route.get_patch(&Route {
+        length: Meters(2.0),
+    });
+    route.length = Meters(3.0);
+
+    let error = patch.apply(&mut route).unwrap_err();
+
+    assert_eq!(error.mismatches()[0].name(), "length");
+}
+
+#[test]
+fn newtype_merge() {
+    let mut first: MetersPatch = Diff::new(&Meters(1.0), &Meters(2.0));
+    let second: MetersPatch = Diff::new(&Meters(2.0), &Meters(3.0));
+    assert_eq!(first.merge(&second), Ok(()));
+
+    let mut obj = Meters(1.0);
+    assert_eq!(first.apply(&mut obj), Ok(()));
+    assert_eq!(obj, Meters(3.0));
+
+    let mut empty = MetersPatch::new();
+    assert_eq!(empty.merge(&second), Ok(()));
+    assert_eq!(empty, second);
+}
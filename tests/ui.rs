@@ -0,0 +1,15 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! The errors of the derives for wrong usage, pinned in tests/ui/*.stderr.
+//!
+//! Run with `TRYBUILD=overwrite` to update the expected errors.
+
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,15 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use derive_patch::Partial;
+
+#[derive(Partial)]
+struct Point {
+    #[partial(default)]
+    x: i32,
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use derive_patch::{diff::CopyDiff, Partial, Patch};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Shape {
+    Circle {
+        #[patch(diff = CopyDiff<u32>)]
+        radius: i32,
+    },
+    Empty,
+}
+
+fn main() {}
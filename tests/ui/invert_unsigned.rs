@@ -0,0 +1,17 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use derive_patch::{diff::NumericDistanceDiff, history::UndoStack, Partial, Patch};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Score {
+    #[patch(diff = NumericDistanceDiff)]
+    points: u32,
+}
+
+fn main() {
+    let _ = UndoStack::<ScorePatch>::new();
+}
@@ -0,0 +1,15 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+use derive_patch::{Partial, Patch};
+
+#[derive(Patch, Partial)]
+union Number {
+    int: i32,
+    float: f32,
+}
+
+fn main() {}
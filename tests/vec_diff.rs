@@ -0,0 +1,95 @@
+/*
+ * Copyright (2020) by Marcel Lambert.
+ * This project's License is the MIT Open Source license.
+ * For more information, see the LICENSE.md file in this repository.
+ */
+
+//! Storing the changes of a vector as an edit script with `VecDiff`.
+
+use derive_patch::{
+    diff::{Diff, VecDiff},
+    traits::{Base, PatchableExt},
+    Partial, Patch,
+};
+
+#[derive(Patch, Partial, Debug, Clone, PartialEq)]
+struct Playlist {
+    #[patch(diff = VecDiff<String>)]
+    songs: Vec<String>,
+}
+
+fn songs(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn stores_only_the_changes() {
+    let old = songs(&["intro", "verse", "chorus", "outro"]);
+    let new = songs(&["intro", "verse", "bridge", "chorus"]);
+
+    let diff: VecDiff<String> = Diff::new(&old, &new);
+
+    assert_eq!(diff.edits().len(), 2);
+    assert_eq!(diff.edits()[0].index(), 2);
+    assert!(diff.edits()[0].removed().is_empty());
+    assert_eq!(diff.edits()[0].inserted(), &songs(&["bridge"])[..]);
+    assert_eq!(diff.edits()[1].index(), 3);
+    assert_eq!(diff.edits()[1].removed(), &songs(&["outro"])[..]);
+    assert!(diff.edits()[1].inserted().is_empty());
+}
+
+#[test]
+fn patch_with_vec_diff() {
+    let mut obj = Playlist {
+        songs: songs(&["a", "b", "c"]),
+    };
+    let new = Playlist {
+        songs: songs(&["a", "x", "c", "d"]),
+    };
+
+    let patch = obj.get_patch(&new);
+
+    assert_eq!(patch.apply(&mut obj), Ok(()));
+    assert_eq!(obj, new);
+}
+
+#[test]
+fn unrelated_changes_still_apply() {
+    let old = songs(&["a", "b", "c", "d"]);
+    let diff: VecDiff<String> = Diff::new(&old, &songs(&["a", "b", "c", "x"]));
+
+    // only the changed element is checked
+    let mut obj = songs(&["z", "b", "c", "d"]);
+    assert_eq!(diff.apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, songs(&["z", "b", "c", "x"]));
+}
+
+#[test]
+fn mismatch_is_named_after_the_index() {
+    let mut obj = Playlist {
+        songs: songs(&["a", "b", "c"]),
+    };
+    let patch = obj.get_patch(&Playlist {
+        songs: songs(&["a", "x", "c"]),
+    });
+    obj.songs[1] = "y".to_string();
+
+    let error = patch.apply(&mut obj).unwrap_err();
+
+    assert_eq!(error.mismatches()[0].path(), "songs.1");
+    assert_eq!(obj.songs, songs(&["a", "y", "c"]));
+}
+
+#[test]
+fn merge_sequential_diffs() {
+    let first = songs(&["a", "b", "c"]);
+    let second = songs(&["b", "c", "d"]);
+    let third = songs(&["b", "x", "d", "e"]);
+
+    let mut diff: VecDiff<String> = Diff::new(&first, &second);
+    assert_eq!(diff.merge(&Diff::new(&second, &third)), Ok(()));
+
+    let mut obj = first;
+    assert_eq!(diff.apply_into(&mut obj), Ok(()));
+    assert_eq!(obj, third);
+}